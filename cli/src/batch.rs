@@ -0,0 +1,234 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+use common::{
+    cards::generate_card_from_spec,
+    model::{Background, CardData, CardSpec},
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::render::{load_spec, read_jacket};
+
+type EntryError = Box<dyn Error + Send + Sync>;
+
+#[derive(Args)]
+pub(crate) struct BatchArgs {
+    /// Manifest of the cards to render, read as CSV if the file ends in .csv and as JSON lines
+    /// otherwise. Jacket paths are relative to the manifest
+    manifest: PathBuf,
+    /// Card spec used for every card, read as TOML if the file ends in .toml and as JSON otherwise
+    #[arg(long)]
+    spec: Option<PathBuf>,
+    /// Background overriding the one in the spec
+    #[arg(long)]
+    background: Option<Background>,
+    /// Directory the cards are written to
+    #[arg(long, default_value = ".")]
+    out_dir: PathBuf,
+    /// File name of each card, where {index}, {track_id}, {name}, {album} and {artists} are
+    /// replaced by the values of its manifest entry
+    #[arg(short, long, default_value = "{index}-{name}.png")]
+    output: String,
+    /// Number of cards rendered at the same time, defaults to the number of CPUs
+    #[arg(short, long)]
+    jobs: Option<usize>,
+    /// Also write the summary as JSON to this file
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct ManifestEntry {
+    #[serde(default)]
+    track_id: String,
+    name: String,
+    #[serde(default)]
+    album: String,
+    #[serde(default = "default_album_type")]
+    album_type: String,
+    #[serde(default)]
+    artists: String,
+    #[serde(default)]
+    genres: String,
+    jacket: PathBuf,
+}
+
+#[derive(Serialize, Default)]
+struct Report {
+    rendered: Vec<PathBuf>,
+    failed: Vec<Failure>,
+}
+
+/// Entry of a manifest, or why the line it is on could not be read as one.
+struct ManifestRow {
+    /// Line of the manifest the entry starts on, starting at 1.
+    line: u64,
+    entry: Result<ManifestEntry, EntryError>,
+}
+
+#[derive(Serialize)]
+struct Failure {
+    /// Position of the entry in the manifest, starting at 1.
+    index: usize,
+    line: u64,
+    /// Empty when the entry could not be read.
+    name: String,
+    error: String,
+}
+
+fn default_album_type() -> String {
+    String::from("album")
+}
+
+/// Reads every row of a manifest, a malformed one only failing its own entry.
+fn read_manifest(path: &Path) -> Result<Vec<ManifestRow>, Box<dyn Error>> {
+    if path.extension().is_some_and(|ext| ext == "csv") {
+        let mut reader = csv::Reader::from_path(path)?;
+        let headers = reader.headers()?.clone();
+        Ok(reader
+            .records()
+            .map(|record| {
+                let position = match &record {
+                    Ok(record) => record.position(),
+                    Err(err) => err.position(),
+                };
+                ManifestRow {
+                    line: position.map_or(0, |position| position.line()),
+                    entry: record
+                        .and_then(|record| record.deserialize(Some(&headers)))
+                        .map_err(Into::into),
+                }
+            })
+            .collect())
+    } else {
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .zip(1..)
+            .filter(|(line, _)| !line.trim().is_empty())
+            .map(|(line, number)| ManifestRow {
+                line: number,
+                entry: serde_json::from_str(line).map_err(Into::into),
+            })
+            .collect())
+    }
+}
+
+/// Fills the placeholders of `template`, dropping characters that are not allowed in file names.
+fn file_name(template: &str, index: &str, entry: &ManifestEntry) -> String {
+    let sanitize = |value: &str| {
+        value
+            .chars()
+            .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+            .collect::<String>()
+    };
+
+    [
+        ("{index}", index),
+        ("{track_id}", &entry.track_id),
+        ("{name}", &entry.name),
+        ("{album}", &entry.album),
+        ("{artists}", &entry.artists),
+    ]
+    .into_iter()
+    .fold(template.to_owned(), |name, (placeholder, value)| {
+        name.replace(placeholder, &sanitize(value))
+    })
+}
+
+fn render_entry(
+    entry: &ManifestEntry, spec: &CardSpec, manifest_dir: &Path, output: &Path,
+) -> Result<(), EntryError> {
+    let (jacket_size, jacket_bytes) = read_jacket(&manifest_dir.join(&entry.jacket))?;
+    let card_data = CardData {
+        track_id: entry.track_id.clone(),
+        name: entry.name.clone(),
+        album: entry.album.clone(),
+        album_type: entry.album_type.clone(),
+        artists: entry.artists.clone(),
+        genres: entry.genres.clone(),
+        jacket_size,
+        jacket_bytes,
+        ..Default::default()
+    };
+
+    fs::write(output, generate_card_from_spec(card_data, spec)?)?;
+    Ok(())
+}
+
+pub(crate) fn run(args: BatchArgs) -> Result<(), Box<dyn Error>> {
+    let mut spec = load_spec(args.spec.as_deref())?;
+    if let Some(background) = args.background {
+        spec.background = background;
+    }
+    let rows = read_manifest(&args.manifest)?;
+    let manifest_dir = args.manifest.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(&args.out_dir)?;
+
+    let index_width = rows.len().to_string().len();
+    // Entries whose file name is already taken fail instead of overwriting an earlier card.
+    let mut taken = HashMap::new();
+    let jobs = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let entry = row.entry.as_ref().map_err(|err| err.to_string())?;
+            let index = format!("{:0index_width$}", i + 1);
+            let output = args.out_dir.join(file_name(&args.output, &index, entry));
+            match taken.entry(output.clone()) {
+                Entry::Occupied(first) => Err(format!(
+                    "{} is already the file name of #{}",
+                    output.display(),
+                    first.get()
+                )
+                .into()),
+                Entry::Vacant(vacant) => {
+                    vacant.insert(i + 1);
+                    Ok((entry, output))
+                }
+            }
+        })
+        .collect::<Vec<Result<(&ManifestEntry, PathBuf), EntryError>>>();
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(args.jobs.unwrap_or(0)).build()?;
+    let results = pool.install(|| {
+        jobs.into_par_iter()
+            .map(|job| {
+                let (entry, output) = job?;
+                render_entry(entry, &spec, manifest_dir, &output).map(|_| output)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut report = Report::default();
+    for (i, (row, result)) in rows.iter().zip(results).enumerate() {
+        match result {
+            Ok(output) => report.rendered.push(output),
+            Err(err) => report.failed.push(Failure {
+                index: i + 1,
+                line: row.line,
+                name: row.entry.as_ref().map_or_else(|_| String::new(), |entry| entry.name.clone()),
+                error: err.to_string(),
+            }),
+        }
+    }
+
+    println!("Rendered {} of {} cards", report.rendered.len(), rows.len());
+    for failure in &report.failed {
+        eprintln!("#{} {} (line {}): {}", failure.index, failure.name, failure.line, failure.error);
+    }
+    if let Some(path) = &args.report {
+        fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    }
+
+    if report.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} of {} cards failed to render", report.failed.len(), rows.len()).into())
+    }
+}
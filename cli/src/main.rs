@@ -0,0 +1,32 @@
+mod batch;
+mod render;
+mod track;
+
+use std::error::Error;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(about = "Generate custom Spotify cards from local files or music catalogs")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render a single card to a PNG file
+    Render(render::RenderArgs),
+    /// Render every card listed in a CSV or JSON lines manifest
+    Batch(batch::BatchArgs),
+    /// Render the card of a track looked up on Deezer, Apple Music or MusicBrainz
+    Track(track::TrackArgs),
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::Render(args) => render::run(args),
+        Command::Batch(args) => batch::run(args),
+        Command::Track(args) => track::run(args),
+    }
+}
@@ -0,0 +1,93 @@
+use std::{error::Error, fs, path::Path, path::PathBuf};
+
+use clap::Args;
+use common::{
+    cards::generate_card_from_spec,
+    model::{Background, CardSpec},
+    tags::AudioTags,
+};
+
+#[derive(Args)]
+pub(crate) struct RenderArgs {
+    /// MP3, FLAC, M4A or Ogg file to take the card fields and jacket from, the other options
+    /// override its tags
+    #[arg(long)]
+    audio: Option<PathBuf>,
+    /// Jacket image
+    #[arg(long, required_unless_present = "audio")]
+    jacket: Option<PathBuf>,
+    #[arg(long, required_unless_present = "audio")]
+    name: Option<String>,
+    #[arg(long)]
+    album: Option<String>,
+    /// Album type as reported by Spotify, the album name is hidden for singles [default: album]
+    #[arg(long)]
+    album_type: Option<String>,
+    #[arg(long)]
+    artists: Option<String>,
+    /// Genres as they are printed, e.g. "#pop #rock"
+    #[arg(long)]
+    genres: Option<String>,
+    /// Card spec, read as TOML if the file ends in .toml and as JSON otherwise
+    #[arg(long)]
+    spec: Option<PathBuf>,
+    /// Background overriding the one in the spec, e.g. plain, blurred or solid-1db954
+    #[arg(long)]
+    background: Option<Background>,
+    #[arg(short, long, default_value = "card.png")]
+    output: PathBuf,
+}
+
+pub(crate) fn load_spec(path: Option<&Path>) -> Result<CardSpec, Box<dyn Error>> {
+    let Some(path) = path else {
+        return Ok(CardSpec::default());
+    };
+
+    let contents = fs::read_to_string(path)?;
+    if path.extension().is_some_and(|ext| ext == "toml") {
+        Ok(toml::from_str(&contents)?)
+    } else {
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// Reads a local jacket, using its width as the jacket size.
+pub(crate) fn read_jacket(path: &Path) -> Result<(u16, Vec<u8>), Box<dyn Error + Send + Sync>> {
+    let (width, _) = image::image_dimensions(path)?;
+    Ok((u16::try_from(width)?, fs::read(path)?))
+}
+
+pub(crate) fn run(args: RenderArgs) -> Result<(), Box<dyn Error>> {
+    let mut spec = load_spec(args.spec.as_deref())?;
+    if let Some(background) = args.background {
+        spec.background = background;
+    }
+
+    let tags = match &args.audio {
+        Some(path) => AudioTags::from_path(path)?,
+        None => AudioTags::default(),
+    };
+    // A jacket given on the command line replaces the embedded cover, keeping its own size.
+    let (jacket_size, cover) = match &args.jacket {
+        Some(path) => {
+            let (size, bytes) = read_jacket(path).map_err(|err| err as Box<dyn Error>)?;
+            (Some(size), Some(bytes))
+        }
+        None => (None, tags.cover),
+    };
+    let mut card_data = AudioTags {
+        title: args.name.or(tags.title),
+        album: args.album.or(tags.album),
+        album_type: args.album_type.or(tags.album_type),
+        artists: args.artists.or(tags.artists),
+        genres: args.genres.or(tags.genres),
+        cover,
+    }
+    .card_data()?;
+    if let Some(jacket_size) = jacket_size {
+        card_data.jacket_size = jacket_size;
+    }
+
+    fs::write(&args.output, generate_card_from_spec(card_data, &spec)?)?;
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::{error::Error, fs, path::PathBuf};
+
+use clap::Args;
+use common::{
+    cards::generate_card_from_spec,
+    model::Background,
+    providers::{
+        apple_music::AppleMusicProvider, deezer::DeezerProvider, musicbrainz::MusicBrainzProvider,
+        MusicProvider,
+    },
+};
+
+use crate::render::load_spec;
+
+#[derive(Args)]
+pub(crate) struct TrackArgs {
+    /// Deezer, Apple Music or MusicBrainz link, Deezer and Apple Music album links included,
+    /// MusicBrainz recording id, `release:` followed by a release id, or ISRC
+    track: String,
+    /// Card spec, read as TOML if the file ends in .toml and as JSON otherwise
+    #[arg(long)]
+    spec: Option<PathBuf>,
+    /// Background overriding the one in the spec, e.g. plain, blurred or solid-1db954
+    #[arg(long)]
+    background: Option<Background>,
+    #[arg(short, long, default_value = "card.png")]
+    output: PathBuf,
+}
+
+/// Providers that work without credentials, in the order they are tried.
+fn providers() -> Vec<Box<dyn MusicProvider + Sync>> {
+    vec![
+        Box::new(DeezerProvider::new()),
+        Box::new(AppleMusicProvider::new()),
+        Box::new(MusicBrainzProvider::new()),
+    ]
+}
+
+pub(crate) fn run(args: TrackArgs) -> Result<(), Box<dyn Error>> {
+    let mut spec = load_spec(args.spec.as_deref())?;
+    if let Some(background) = args.background {
+        spec.background = background;
+    }
+
+    let providers = providers();
+    let Some((provider, id)) = providers
+        .iter()
+        .find_map(|provider| Some((provider, provider.parse_track_id(&args.track)?)))
+    else {
+        return Err(format!("{} is not a track link or id", args.track).into());
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    let card_data = runtime.block_on(provider.card(&id))?;
+    let name = card_data.name.clone();
+    fs::write(&args.output, generate_card_from_spec(card_data, &spec)?)?;
+    println!("Rendered {name} from {}", provider.name());
+    Ok(())
+}
@@ -0,0 +1,112 @@
+mod support;
+
+use std::fs;
+
+use serde_json::Value;
+
+use support::{jacket_png, run, TempDir};
+
+#[test]
+fn batch_renders_every_entry_of_a_csv_manifest() {
+    let dir = TempDir::new("batch-csv");
+    dir.write("cover.png", jacket_png());
+    dir.write(
+        "tracks.csv",
+        "track_id,name,album,album_type,artists,genres,jacket\n\
+         1,First Song,Album,album,Artist,#pop,cover.png\n\
+         2,Second Song,Album,single,Artist,#rock,cover.png\n",
+    );
+
+    let output = run(&dir, &["batch", "tracks.csv", "--out-dir", "cards"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    for name in ["1-First Song.png", "2-Second Song.png"] {
+        let card = image::open(dir.path().join("cards").join(name)).unwrap();
+        assert!(card.width() > 64, "{name}");
+    }
+}
+
+#[test]
+fn batch_reports_failed_entries_and_keeps_going() {
+    let dir = TempDir::new("batch-failures");
+    dir.write("cover.png", jacket_png());
+    dir.write("broken.png", "not an image");
+    dir.write(
+        "tracks.jsonl",
+        [
+            r#"{"name": "First Song", "artists": "Artist", "jacket": "cover.png"}"#,
+            r#"{"name": "Broken Song", "artists": "Artist", "jacket": "broken.png"}"#,
+            r#"{"name": "Second Song", "artists": "Artist", "jacket": "cover.png"}"#,
+            r#"{"name": "First Song", "artists": "Someone Else", "jacket": "cover.png"}"#,
+        ]
+        .join("\n"),
+    );
+
+    let args = ["batch", "tracks.jsonl", "-o", "{name}.png", "--report", "report.json"];
+    let output = run(&dir, &args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Rendered 2 of 4 cards"));
+
+    let report: Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("report.json")).unwrap()).unwrap();
+    let rendered = report["rendered"].as_array().unwrap();
+    assert_eq!(rendered, &["./First Song.png", "./Second Song.png"]);
+    let failed = report["failed"].as_array().unwrap();
+    assert_eq!(failed.len(), 2);
+    assert_eq!(failed[0]["index"], 2);
+    assert_eq!(failed[0]["name"], "Broken Song");
+    assert_eq!(failed[1]["index"], 4);
+    assert_eq!(failed[1]["name"], "First Song");
+    assert!(failed[1]["error"].as_str().unwrap().contains("#1"), "{}", failed[1]["error"]);
+
+    // The first card was not overwritten by the later entry with the same name.
+    dir.write(
+        "first.jsonl",
+        r#"{"name": "First Song", "artists": "Artist", "jacket": "cover.png"}"#,
+    );
+    let output = run(&dir, &["batch", "first.jsonl", "-o", "{name}.png", "--out-dir", "alone"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let first = fs::read(dir.path().join("First Song.png")).unwrap();
+    assert!(first == fs::read(dir.path().join("alone/First Song.png")).unwrap());
+}
+
+#[test]
+fn batch_reports_unreadable_rows_with_their_line() {
+    let dir = TempDir::new("batch-unreadable");
+    dir.write("cover.png", jacket_png());
+    dir.write(
+        "tracks.csv",
+        "track_id,name,album,album_type,artists,genres,jacket\n\
+         1,First Song,Album,album,Artist,#pop,cover.png\n\
+         2,Too Short\n\
+         3,Second Song,Album,album,Artist,#pop,cover.png\n",
+    );
+    dir.write(
+        "tracks.jsonl",
+        [
+            r#"{"name": "First Song", "jacket": "cover.png"}"#,
+            "",
+            r#"{"name": "No Jacket"}"#,
+            r#"{"name": "Second Song", "jacket": "cover.png"}"#,
+        ]
+        .join("\n"),
+    );
+
+    // Both manifests break on their third line, the header and the blank line counting too.
+    for (manifest, out_dir) in [("tracks.csv", "csv"), ("tracks.jsonl", "jsonl")] {
+        let args = ["batch", manifest, "--out-dir", out_dir, "--report", "report.json"];
+        let output = run(&dir, &args);
+        assert!(!output.status.success(), "{manifest}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Rendered 2 of 3 cards"), "{manifest}: {stdout}");
+
+        let report: Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("report.json")).unwrap())
+                .unwrap();
+        assert_eq!(report["rendered"].as_array().unwrap().len(), 2, "{manifest}");
+        let failed = report["failed"].as_array().unwrap();
+        assert_eq!(failed.len(), 1, "{manifest}");
+        assert_eq!(failed[0]["index"], 2, "{manifest}");
+        assert_eq!(failed[0]["line"], 3, "{manifest}");
+        assert_eq!(failed[0]["name"], "", "{manifest}");
+    }
+}
@@ -0,0 +1,42 @@
+mod support;
+
+use std::fs;
+
+use image::{GenericImageView, ImageFormat};
+
+use support::{jacket_png, run, TempDir};
+
+#[test]
+fn render_writes_a_png_card() {
+    let dir = TempDir::new("render");
+    dir.write("cover.png", jacket_png());
+    dir.write("spec.json", r#"{ "background": "plain", "fields": { "genres": false } }"#);
+
+    let args = ["render", "--jacket", "cover.png", "--name", "Song", "--artists", "Artist"];
+    let output = run(&dir, &[&args[..], &["--spec", "spec.json", "-o", "card.png"]].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = run(&dir, &[&args[..], &["-o", "default.png"]].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let card = fs::read(dir.path().join("card.png")).unwrap();
+    assert_eq!(image::guess_format(&card).unwrap(), ImageFormat::Png);
+    let card = image::load_from_memory(&card).unwrap();
+    let (width, height) = card.dimensions();
+    assert!(width > 64 && height > 64, "card is {width}x{height}");
+    // A plain background is a single color, unlike the default gradient.
+    assert_eq!(card.get_pixel(width - 1, 0), card.get_pixel(width - 1, height - 1));
+    let default = image::open(dir.path().join("default.png")).unwrap();
+    assert_ne!(default.get_pixel(width - 1, 0), default.get_pixel(width - 1, height - 1));
+}
+
+#[test]
+fn render_reports_invalid_specs() {
+    let dir = TempDir::new("render-invalid-spec");
+    dir.write("cover.png", jacket_png());
+    dir.write("spec.json", r#"{ "background": "sparkles" }"#);
+
+    let args = ["render", "--jacket", "cover.png", "--name", "Song", "--spec", "spec.json"];
+    let output = run(&dir, &args);
+    assert!(!output.status.success());
+    assert!(!dir.path().join("card.png").exists());
+}
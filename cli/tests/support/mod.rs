@@ -0,0 +1,58 @@
+//! Helpers shared by the command line tests.
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+
+/// 64px jacket encoded as PNG.
+pub fn jacket_png() -> Vec<u8> {
+    let jacket = RgbaImage::from_fn(64, 64, |x, y| Rgba([x as u8 * 4, y as u8 * 4, 128, 255]));
+    let mut bytes = Vec::new();
+    jacket.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png).unwrap();
+    bytes
+}
+
+/// Directory under the system temporary directory, removed with everything in it when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory, `name` keeping tests running in parallel apart.
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("spotify-cards-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes `contents` to `name` in the directory and returns its path.
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs the command line with `args` from `dir`.
+pub fn run(dir: &TempDir, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_spotify-cards"))
+        .current_dir(dir.path())
+        .args(args)
+        .output()
+        .unwrap()
+}
@@ -0,0 +1,69 @@
+use std::io::Cursor;
+
+use common::{
+    cards::generate_canvas_assets,
+    model::{CardData, CardSpec, PaletteAlgorithm, PaletteOptions},
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use image::{DynamicImage, Rgb, RgbImage};
+
+/// Card data with a 640x640 jacket, the size Spotify serves, full of distinct colors.
+fn card_data() -> CardData {
+    let jacket = RgbImage::from_fn(640, 640, |x, y| {
+        Rgb([(x * 255 / 640) as u8, (y * 255 / 640) as u8, ((x + y) % 256) as u8])
+    });
+    let mut jacket_bytes = vec![];
+    DynamicImage::ImageRgb8(jacket)
+        .write_to(&mut Cursor::new(&mut jacket_bytes), image::ImageOutputFormat::Png)
+        .unwrap();
+
+    CardData {
+        track_id: String::from("bench"),
+        name: String::from("Name"),
+        album: String::from("Album"),
+        album_type: String::from("album"),
+        artists: String::from("Artist"),
+        genres: String::from("#genre"),
+        jacket_size: 640,
+        jacket_bytes,
+        ..Default::default()
+    }
+}
+
+fn spec(algorithm: PaletteAlgorithm, sample_size: Option<u32>) -> CardSpec {
+    CardSpec {
+        palette: PaletteOptions { algorithm, sample_size, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+fn sample_sizes(c: &mut Criterion) {
+    let card_data = card_data();
+    let mut group = c.benchmark_group("k-means sample size");
+    group.sample_size(10);
+    for sample_size in [Some(64), Some(128), Some(256), None] {
+        let spec = spec(PaletteAlgorithm::KMeans, sample_size);
+        let label = sample_size.map_or(String::from("full"), |size| size.to_string());
+        group.bench_with_input(BenchmarkId::from_parameter(label), &spec, |b, spec| {
+            b.iter(|| generate_canvas_assets(card_data.clone(), spec).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn algorithms(c: &mut Criterion) {
+    let card_data = card_data();
+    let mut group = c.benchmark_group("palette algorithm");
+    group.sample_size(10);
+    for algorithm in [PaletteAlgorithm::KMeans, PaletteAlgorithm::MedianCut, PaletteAlgorithm::Octree]
+    {
+        let spec = spec(algorithm, PaletteOptions::default().sample_size);
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{algorithm:?}")), &spec, |b, spec| {
+            b.iter(|| generate_canvas_assets(card_data.clone(), spec).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sample_sizes, algorithms);
+criterion_main!(benches);
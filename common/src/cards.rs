@@ -1,73 +1,267 @@
-use std::io::Cursor;
+use std::{fmt, io::Cursor, ops::RangeInclusive};
 
-use crate::model::CardData;
+use crate::{
+    colors::{PaletteExtractor, Swatches},
+    model::{
+        Background, BrightnessRange, BundledFont, CardData, CardFields, CardKind, CardLayout,
+        CardSpec, ColorStop, ContrastFallback, ContrastOptions, Dithering, GradientShape,
+        PaletteOptions,
+    },
+};
 use image::{
     imageops::{overlay, vertical_gradient, FilterType},
-    DynamicImage, Pixel, Rgba,
+    DynamicImage, GenericImageView, GrayImage, Luma, Pixel, Rgba, RgbaImage,
 };
 use imageproc::drawing::{draw_text_mut, text_size};
-use kmeans_colors::{CentroidData, Sort};
+use kmeans_colors::CentroidData;
 use palette::{rgb::Rgb, IntoColor, Lab, Srgb};
 use rusttype::{point, Font, Scale};
 
-const JACKET_OFFSET: u32 = 30;
-const TEXT_OFFSET_Y: u32 = 30;
-const TEXT_SPACING: u32 = 60;
 const WHITE: Rgba<u8> = Rgba([255; 4]);
 const BLACK: Rgba<u8> = Rgba([0, 0, 0, 191]);
+const OPAQUE_BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const LIGHT: Rgba<u8> = Rgba([255, 255, 255, 191]);
+/// Centroids are indexed with a `u8`, so no extractor can return more colors than this.
+const MAX_PALETTE_COLORS: usize = 256;
+const MAX_CONTRAST: f32 = 21.0;
+/// Bounds of the layouts [`CardSpec::validate`] accepts, which keep the canvas of a 640px jacket
+/// within a few thousand pixels.
+const JACKET_SCALES: RangeInclusive<f32> = 0.1..=2.0;
+const TEXT_AREA_RATIOS: RangeInclusive<f32> = 0.1..=4.0;
+const TEXT_SCALES: RangeInclusive<f32> = 1.0..=200.0;
+const MAX_LAYOUT_OFFSET: u32 = 1000;
+/// Genres are drawn this far above the bottom of the canvas, within the margin of the jacket.
+const GENRES_MARGIN: u32 = 15;
+const MAX_TRACKLIST_LENGTH: usize = 50;
+/// Bounds of the k-means work [`CardSpec::validate`] accepts, about 20 times the default.
+const MAX_KMEANS_ITERATIONS: usize = 500;
+const MAX_KMEANS_RUNS: u64 = 64;
+const MAX_SAMPLE_SIZE: u32 = 4096;
+const OUTLINE_OFFSETS: [(i32, i32); 8] =
+    [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
 const TRANSPARENT: Rgba<u8> = Rgba([0; 4]);
-const TEXT_SCALE: f32 = 60.0;
-const GENRES_SCALE: f32 = 10.0;
+const BLUR_DOWNSCALE: u32 = 4;
+const BLUR_SIGMA: f32 = 6.0;
+const TINT_ALPHA: u8 = 170;
+/// Height of badges relative to the scale of their line, and of their letter relative to that.
+const BADGE_SIZE: f32 = 0.8;
+const BADGE_TEXT_SIZE: f32 = 0.7;
+/// Space between the text of a line and its badge.
+const BADGE_GAP: u32 = 10;
+const EXPLICIT_BADGE: &str = "E";
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
 const DEFAULT_FONT: &[u8] = include_bytes!("../../common/assets/Montserrat-Bold.ttf");
 const JAPANESE_FONT: &[u8] = include_bytes!("../../common/assets/MPLUS2-Bold.ttf");
 const REGEX_JA: &str =
     r"[\u3040-\u30ff\u3400-\u4dbf\u4e00-\u9fff\uf900-\ufaff\uff66-\uff9f\u3131-\uD79D]";
 
+#[derive(Debug)]
+pub enum CardError {
+    /// The jacket bytes could not be decoded into an image.
+    JacketDecode(image::ImageError),
+    /// No usable colors were left after filtering the jacket palette.
+    EmptyPalette,
+    /// One of the bundled fonts failed to load.
+    Font,
+    /// A text field has no visible glyphs to draw, e.g. it only contains spaces.
+    EmptyText(String),
+    /// A color given in a [`CardSpec`] is not a valid `#rrggbb` hex string.
+    InvalidColor(String),
+    /// The number of palette colors asked for in a [`CardSpec`] is not between 1 and 256.
+    InvalidPaletteSize(usize),
+    /// A layout, palette or contrast option of a [`CardSpec`] is out of bounds, see
+    /// [`CardSpec::validate`].
+    InvalidSpec(String),
+    /// The final card could not be encoded as PNG.
+    Encode(image::ImageError),
+}
+
+impl fmt::Display for CardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardError::JacketDecode(err) => write!(f, "could not decode jacket image: {err}"),
+            CardError::EmptyPalette => write!(f, "could not find any usable color in the jacket"),
+            CardError::Font => write!(f, "could not load font"),
+            CardError::EmptyText(text) => write!(f, "text {text:?} has no visible glyphs"),
+            CardError::InvalidColor(color) => write!(f, "invalid hex color {color:?}"),
+            CardError::InvalidPaletteSize(colors) => {
+                write!(f, "palettes hold from 1 to 256 colors, not {colors}")
+            }
+            CardError::InvalidSpec(reason) => write!(f, "invalid card spec: {reason}"),
+            CardError::Encode(err) => write!(f, "could not encode card: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CardError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CardError::JacketDecode(err) | CardError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Fails with [`CardError::InvalidSpec`] unless `value` is within `range`, which NaN never is.
+fn check_range<T: PartialOrd + fmt::Display>(
+    option: &str, value: T, range: RangeInclusive<T>,
+) -> Result<(), CardError> {
+    if range.contains(&value) {
+        return Ok(());
+    }
+    let (min, max) = range.into_inner();
+    Err(CardError::InvalidSpec(format!("{option} must be from {min} to {max}, not {value}")))
+}
+
+impl CardSpec {
+    /// Checks that the card can be drawn from this spec in bounded time and memory, which specs
+    /// sent by untrusted clients may not allow: a zero `layout.jacket_scale` or a huge
+    /// `palette.runs`, say. Colors are parsed too, so a bad hex fails before any rendering.
+    pub fn validate(&self) -> Result<(), CardError> {
+        let layout = &self.layout;
+        check_range("layout.jacket_scale", layout.jacket_scale, JACKET_SCALES)?;
+        check_range("layout.text_area_ratio", layout.text_area_ratio, TEXT_AREA_RATIOS)?;
+        for (option, scale) in [
+            ("layout.text_scale", layout.text_scale),
+            ("layout.genres_scale", layout.genres_scale),
+            ("layout.details_scale", layout.details_scale),
+            ("layout.list_scale", layout.list_scale),
+        ] {
+            check_range(option, scale, TEXT_SCALES)?;
+        }
+        // The genres line needs the margin around the jacket.
+        let jacket_offsets = GENRES_MARGIN..=MAX_LAYOUT_OFFSET;
+        check_range("layout.jacket_offset", layout.jacket_offset, jacket_offsets)?;
+        for (option, offset) in [
+            ("layout.text_offset_y", layout.text_offset_y),
+            ("layout.text_spacing", layout.text_spacing),
+            ("layout.list_spacing", layout.list_spacing),
+        ] {
+            check_range(option, offset, 0..=MAX_LAYOUT_OFFSET)?;
+        }
+        check_range("layout.tracklist_length", layout.tracklist_length, 0..=MAX_TRACKLIST_LENGTH)?;
+
+        let palette = &self.palette;
+        if !(1..=MAX_PALETTE_COLORS).contains(&palette.colors) {
+            return Err(CardError::InvalidPaletteSize(palette.colors));
+        }
+        check_range("palette.iterations", palette.iterations, 1..=MAX_KMEANS_ITERATIONS)?;
+        check_range("palette.runs", palette.runs, 1..=MAX_KMEANS_RUNS)?;
+        if let Some(sample_size) = palette.sample_size {
+            check_range("palette.sample_size", sample_size, 1..=MAX_SAMPLE_SIZE)?;
+        }
+        check_range("palette.converge", palette.converge, 0.0..=f32::MAX)?;
+        for (option, range) in [
+            ("palette.gradient_brightness", palette.gradient_brightness),
+            ("palette.plain_brightness", palette.plain_brightness),
+        ] {
+            check_range(&format!("{option}.min"), range.min, 0.0..=3.0)?;
+            check_range(&format!("{option}.max"), range.max, 0.0..=3.0)?;
+        }
+        check_range("contrast.min_ratio", self.contrast.min_ratio, 1.0..=MAX_CONTRAST)?;
+
+        if let Background::Solid(hex) = &self.background {
+            parse_hex_color(hex)?;
+        }
+        for ColorStop { color, position } in self.colors.custom_stops.iter().flatten() {
+            parse_hex_color(color)?;
+            check_range("colors.custom_stops.position", *position, 0.0..=1.0)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ColorSelectorEmit {
     pub new_color: Rgba<u8>,
     pub row: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    pub color: Rgba<u8>,
+    pub position: f32,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct GradientColors {
     pub plain: Rgba<u8>,
     pub gradient: Option<(Rgba<u8>, Rgba<u8>)>,
     pub custom_gradient: Option<(Rgba<u8>, Rgba<u8>)>,
+    /// Optional color halfway through the custom gradient.
+    pub custom_middle: Option<Rgba<u8>>,
     pub all_colors: Vec<Rgba<u8>>,
+    pub swatches: Swatches,
 }
 
 impl GradientColors {
-    pub fn gradient_blend(&self, is_custom_gradient: bool) -> Rgba<u8> {
-        let (start, end) = match is_custom_gradient {
-            false => self.gradient.unwrap(),
-            _ => self.custom_gradient.unwrap(),
-        };
+    /// Start and end of the extracted gradient, or of the custom one. Very dark or very bright
+    /// jackets may have no color in the gradient brightness range.
+    fn gradient_pair(&self, is_custom_gradient: bool) -> Result<(Rgba<u8>, Rgba<u8>), CardError> {
+        match is_custom_gradient {
+            false => self.gradient,
+            _ => self.custom_gradient,
+        }
+        .ok_or(CardError::EmptyPalette)
+    }
+
+    pub fn gradient_blend(&self, is_custom_gradient: bool) -> Result<Rgba<u8>, CardError> {
+        let (start, end) = self.gradient_pair(is_custom_gradient)?;
 
         let mut blend = start.clone();
         blend.blend(&Rgba([end[0], end[1], end[2], 127]));
-        blend
+        Ok(blend)
     }
 
-    pub fn gradient_custom_blend(&self, blend_factor: u8) -> Rgba<u8> {
-        let (start, end) = self.gradient.unwrap();
+    pub fn gradient_custom_blend(&self, blend_factor: u8) -> Result<Rgba<u8>, CardError> {
+        let (start, end) = self.gradient_pair(false)?;
         let mut blend = start.clone();
         blend.blend(&Rgba([end[0], end[1], end[2], blend_factor]));
-        blend
+        Ok(blend)
     }
 
-    pub fn gradient_start(&self, is_custom_gradient: bool) -> Rgba<u8> {
-        match is_custom_gradient {
-            false => self.gradient.unwrap().0,
-            _ => self.custom_gradient.unwrap().0,
-        }
+    pub fn gradient_start(&self, is_custom_gradient: bool) -> Result<Rgba<u8>, CardError> {
+        Ok(self.gradient_pair(is_custom_gradient)?.0)
     }
 
-    pub fn gradient_end(&self, is_custom_gradient: bool) -> Rgba<u8> {
-        match is_custom_gradient {
-            false => self.gradient.unwrap().1,
-            _ => self.custom_gradient.unwrap().1,
-        }
+    pub fn gradient_end(&self, is_custom_gradient: bool) -> Result<Rgba<u8>, CardError> {
+        Ok(self.gradient_pair(is_custom_gradient)?.1)
+    }
+
+    /// Color stops of the gradient drawn for `background`.
+    pub fn background_stops(&self, background: &Background) -> Result<Vec<GradientStop>, CardError> {
+        let stop = |color, position| GradientStop { color, position };
+        Ok(match background {
+            Background::Plain => vec![stop(self.plain, 0.0)],
+            Background::Gradient => {
+                vec![stop(self.gradient_start(false)?, 0.0), stop(self.gradient_end(false)?, 1.0)]
+            }
+            Background::Inverted => {
+                vec![stop(self.gradient_end(false)?, 0.0), stop(self.gradient_start(false)?, 1.0)]
+            }
+            Background::Custom => {
+                let (start, end) = self.gradient_pair(true)?;
+                let middle = self.custom_middle.map(|color| stop(color, 0.5));
+                [Some(stop(start, 0.0)), middle, Some(stop(end, 1.0))]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+            Background::BlurredJacket => vec![
+                stop(darken(self.gradient_start(false)?), 0.0),
+                stop(darken(self.gradient_end(false)?), 1.0),
+            ],
+            Background::Solid(hex) => vec![stop(parse_hex_color(hex)?, 0.0)],
+        })
     }
 }
 
@@ -76,6 +270,7 @@ pub struct CanvasAssets {
     pub jacket_size: u32,
     pub jacket: DynamicImage,
     pub colors: GradientColors,
+    pub layout: CardLayout,
 }
 
 impl CanvasAssets {
@@ -84,19 +279,37 @@ impl CanvasAssets {
     }
 
     pub fn text_offset_x(&self) -> u32 {
-        self.jacket_size + JACKET_OFFSET * 2 - 5
+        (self.jacket_size + self.layout.jacket_offset * 2).saturating_sub(5)
     }
 
     pub fn text_area_width(&self) -> u32 {
-        (self.jacket_size as f32 * 0.84).round() as u32
+        (self.jacket_size as f32 * self.layout.text_area_ratio).round() as u32
     }
 
     pub fn canvas_height(&self) -> u32 {
-        self.jacket_size + JACKET_OFFSET * 2
+        self.jacket_size + self.layout.jacket_offset * 2
     }
 
     pub fn canvas_width(&self) -> u32 {
-        self.jacket_size + self.text_area_width() + JACKET_OFFSET * 3
+        self.jacket_size + self.text_area_width() + self.layout.jacket_offset * 3
+    }
+}
+
+/// Color of a text and, when that color alone is not readable enough, what is drawn around it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub color: Rgba<u8>,
+    pub fallback: Option<ContrastFallback>,
+}
+
+impl TextStyle {
+    /// Color used for the outline, shadow or backdrop of the text.
+    fn contrasting_color(&self) -> Rgba<u8> {
+        if self.color == WHITE {
+            BLACK
+        } else {
+            LIGHT
+        }
     }
 }
 
@@ -108,7 +321,414 @@ pub struct TextAssets {
     pub regex: regex::Regex,
 }
 
-fn luminance(color: &Rgba<u8>) -> f32 {
+/// Parses a `#rrggbb` (or `rrggbb`) hex string into an opaque color.
+pub fn parse_hex_color(hex: &str) -> Result<Rgba<u8>, CardError> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let channel = |i: usize| {
+        digits
+            .get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .ok_or_else(|| CardError::InvalidColor(hex.to_owned()))
+    };
+    if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(CardError::InvalidColor(hex.to_owned()));
+    }
+
+    Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, 255]))
+}
+
+fn darken(color: Rgba<u8>) -> Rgba<u8> {
+    let mut darkened = color;
+    darkened.blend(&Rgba([0, 0, 0, 64]));
+    darkened
+}
+
+/// Offset in `-0.5..0.5` added to a pixel before rounding it to 8 bits.
+fn dither_offset(dithering: Dithering, x: u32, y: u32) -> f32 {
+    match dithering {
+        Dithering::None => 0.0,
+        Dithering::Bayer => {
+            (BAYER_8X8[y as usize % 8][x as usize % 8] as f32 + 0.5) / 64.0 - 0.5
+        }
+    }
+}
+
+/// Unquantized color at position `t` of the gradient.
+fn gradient_color(stops: &[GradientStop], t: f32) -> [f32; 4] {
+    let to_f32 = |color: Rgba<u8>| color.0.map(f32::from);
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return to_f32(TRANSPARENT),
+    };
+    if t <= first.position {
+        return to_f32(first.color);
+    }
+
+    stops
+        .windows(2)
+        .find(|pair| t <= pair[1].position)
+        .map(|pair| {
+            let (from, to) = (pair[0], pair[1]);
+            let span = to.position - from.position;
+            let factor = if span > 0.0 { (t - from.position) / span } else { 1.0 };
+            std::array::from_fn(|c| {
+                from.color[c] as f32 + (to.color[c] as f32 - from.color[c] as f32) * factor
+            })
+        })
+        .unwrap_or(to_f32(last.color))
+}
+
+/// Paints `stops` following `shape` over a `width`x`height` image.
+fn gradient_image(
+    width: u32, height: u32, shape: &GradientShape, stops: &[GradientStop], dithering: Dithering,
+) -> RgbaImage {
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+    let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+
+    let position = |x: u32, y: u32| {
+        let (dx, dy) = (x as f32 + 0.5 - center_x, y as f32 + 0.5 - center_y);
+        match *shape {
+            GradientShape::Linear { angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let length = (width as f32 * sin).abs() + (height as f32 * cos).abs();
+                0.5 + (dx * sin - dy * cos) / length
+            }
+            GradientShape::Radial => dx.hypot(dy) / center_x.hypot(center_y),
+            GradientShape::Conic { angle } => {
+                let turn = std::f32::consts::TAU;
+                (dx.atan2(-dy) - angle.to_radians()).rem_euclid(turn) / turn
+            }
+        }
+    };
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let offset = dither_offset(dithering, x, y);
+        Rgba(gradient_color(&stops, position(x, y)).map(|c| (c + offset).round().clamp(0.0, 255.0) as u8))
+    })
+}
+
+/// Luminance of every pixel in the canvas area a text box is about to be drawn on.
+fn region_luminances(canvas: &DynamicImage, x: i64, y: i64, width: u32, height: u32) -> Vec<f32> {
+    let clamp_x = |v: i64| v.clamp(0, canvas.width() as i64) as u32;
+    let clamp_y = |v: i64| v.clamp(0, canvas.height() as i64) as u32;
+    let (min_x, max_x) = (clamp_x(x), clamp_x(x + width as i64));
+    let (min_y, max_y) = (clamp_y(y), clamp_y(y + height as i64));
+
+    (min_y..max_y)
+        .flat_map(|pixel_y| (min_x..max_x).map(move |pixel_x| (pixel_x, pixel_y)))
+        .map(|(pixel_x, pixel_y)| luminance(&canvas.get_pixel(pixel_x, pixel_y)))
+        .collect()
+}
+
+/// WCAG contrast ratio between two relative luminances, from 1.0 to 21.0.
+pub fn contrast_ratio(a: f32, b: f32) -> f32 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// WCAG contrast `color` reaches against all but the least favorable 10% of `backdrop`.
+fn reached_contrast(color: &Rgba<u8>, backdrop: &[f32]) -> f32 {
+    let text_luminance = luminance(color);
+    let mut ratios =
+        backdrop.iter().map(|l| contrast_ratio(text_luminance, *l)).collect::<Vec<_>>();
+    ratios.sort_by(f32::total_cmp);
+    ratios.get(ratios.len() / 10).copied().unwrap_or(MAX_CONTRAST)
+}
+
+/// Picks white or black, whichever contrasts more with `backdrop`, falling back to an extra
+/// effect when even that color does not reach the minimum ratio.
+pub fn text_style(backdrop: &[f32], contrast: &ContrastOptions) -> TextStyle {
+    let white = reached_contrast(&WHITE, backdrop);
+    let black = reached_contrast(&OPAQUE_BLACK, backdrop);
+    let (color, ratio) = if white >= black { (WHITE, white) } else { (OPAQUE_BLACK, black) };
+
+    TextStyle { color, fallback: (ratio < contrast.min_ratio).then_some(contrast.fallback) }
+}
+
+/// Scales the jacket to cover the whole canvas, blurs it and lays the tint gradient on top.
+///
+/// The blur runs on a downscaled copy, which is much cheaper and indistinguishable once
+/// scaled back up.
+fn blurred_jacket_background(
+    jacket: &DynamicImage, width: u32, height: u32, spec: &CardSpec, tint: &[GradientStop],
+) -> DynamicImage {
+    let tint = tint
+        .iter()
+        .map(|stop| GradientStop {
+            color: Rgba([stop.color[0], stop.color[1], stop.color[2], TINT_ALPHA]),
+            ..*stop
+        })
+        .collect::<Vec<_>>();
+
+    let small_jacket = jacket.resize_to_fill(
+        (width / BLUR_DOWNSCALE).max(1),
+        (height / BLUR_DOWNSCALE).max(1),
+        FilterType::Triangle,
+    );
+    let mut background = DynamicImage::ImageRgba8(
+        small_jacket.blur(BLUR_SIGMA).resize_exact(width, height, FilterType::Triangle).into_rgba8(),
+    );
+
+    let tint_layer = gradient_image(width, height, &spec.gradient, &tint, spec.dithering);
+    overlay(&mut background, &tint_layer, 0, 0);
+    background
+}
+
+/// Formats a color as a `#rrggbb` hex string, ignoring its alpha.
+pub fn to_hex_color(color: &Rgba<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn font_bytes(font: BundledFont) -> &'static [u8] {
+    match font {
+        BundledFont::Montserrat => DEFAULT_FONT,
+        BundledFont::MPlus2 => JAPANESE_FONT,
+    }
+}
+
+/// Size and position of a line of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineRole {
+    /// Large text in the column next to the jacket, like the name of the track.
+    Main,
+    /// Smaller text in the same column, like the release year of an album.
+    Details,
+    /// Line of a list drawn close to the next one, like the tracks of a playlist.
+    Listed,
+    /// Tiny text at the bottom of the column, where the genres go.
+    Footer,
+}
+
+impl LineRole {
+    fn scale(self, layout: &CardLayout) -> Scale {
+        Scale::uniform(match self {
+            LineRole::Main => layout.text_scale,
+            LineRole::Details => layout.details_scale,
+            LineRole::Listed => layout.list_scale,
+            LineRole::Footer => layout.genres_scale,
+        })
+    }
+
+    /// Space left below the line.
+    fn spacing(self, layout: &CardLayout) -> u32 {
+        match self {
+            LineRole::Listed => layout.list_spacing,
+            _ => layout.text_spacing,
+        }
+    }
+
+    /// Number of characters after which list lines are cut short, lists are not wrapped.
+    const LIST_WIDTH: usize = 42;
+
+    /// Number of characters after which the text is wrapped.
+    fn wrap_width(self) -> usize {
+        match self {
+            LineRole::Listed | LineRole::Footer => 10000,
+            _ => 36,
+        }
+    }
+}
+
+/// Text drawn next to the jacket. Hidden lines take their room in the column without being drawn.
+#[derive(Debug, Clone, PartialEq)]
+struct CardLine {
+    text: String,
+    role: LineRole,
+    hidden: bool,
+    /// Label drawn in a box after the text, like the explicit badge of tracks.
+    badge: Option<&'static str>,
+}
+
+/// Lines drawn for the kind of `card_data`, with disabled fields left empty.
+fn card_lines(card_data: &CardData, fields: &CardFields, layout: &CardLayout) -> Vec<CardLine> {
+    let line = |text: &str, is_enabled: bool, role: LineRole| CardLine {
+        text: if is_enabled { text.to_owned() } else { String::new() },
+        role,
+        hidden: false,
+        badge: None,
+    };
+
+    let year = card_data.release_date.as_deref().and_then(|date| date.get(..4));
+    let track_count = card_data.total_tracks.map(|total| match total {
+        1 => String::from("1 track"),
+        total => format!("{total} tracks"),
+    });
+    let followers = card_data.followers.map(|followers| match followers {
+        1 => String::from("1 follower"),
+        followers => format!("{} followers", format_count(followers)),
+    });
+    let tracklist = card_data.tracklist.iter().take(layout.tracklist_length).map(|track| {
+        let text = match track.artists.as_str() {
+            "" => track.name.clone(),
+            artists => format!("{} — {artists}", track.name),
+        };
+        line(&truncate(&text, LineRole::LIST_WIDTH), fields.tracklist, LineRole::Listed)
+    });
+
+    match card_data.kind {
+        CardKind::Track => {
+            let position = card_data.track_number.map(|number| match card_data.total_tracks {
+                Some(total) => format!("{number}/{total}"),
+                None => format!("Track {number}"),
+            });
+            let summary = [
+                card_data.duration_ms.filter(|_| fields.duration).map(format_time),
+                year.filter(|_| fields.release_year).map(str::to_owned),
+                position.filter(|_| fields.track_number),
+            ];
+            let summary = summary.into_iter().flatten().collect::<Vec<_>>().join(" · ");
+
+            vec![
+                line(&card_data.name, fields.name, LineRole::Main),
+                // Singles are named after their track, the album is left out but keeps its room.
+                CardLine {
+                    hidden: card_data.album_type == "single",
+                    ..line(&card_data.album, fields.album, LineRole::Main)
+                },
+                line(&card_data.artists, fields.artists, LineRole::Main),
+                CardLine {
+                    badge: (card_data.explicit && fields.explicit).then_some(EXPLICIT_BADGE),
+                    ..line(&summary, true, LineRole::Details)
+                },
+                line(&card_data.genres, fields.genres, LineRole::Footer),
+            ]
+        }
+        CardKind::Album => {
+            let summary = [
+                year.filter(|_| fields.release_year).map(str::to_owned),
+                track_count.filter(|_| fields.track_count),
+                card_data.duration_ms.filter(|_| fields.duration).map(format_duration),
+            ];
+            let summary = summary.into_iter().flatten().collect::<Vec<_>>().join(" · ");
+
+            vec![
+                line(&card_data.name, fields.name, LineRole::Main),
+                line(&card_data.artists, fields.artists, LineRole::Main),
+                line(&summary, true, LineRole::Details),
+                line(
+                    card_data.label.as_deref().unwrap_or_default(),
+                    fields.label,
+                    LineRole::Details,
+                ),
+                line(&card_data.genres, fields.genres, LineRole::Footer),
+            ]
+        }
+        CardKind::Playlist => {
+            let summary = [
+                card_data.owner.as_ref().filter(|_| fields.owner).map(|owner| {
+                    format!("by {owner}")
+                }),
+                followers.filter(|_| fields.followers),
+                track_count.filter(|_| fields.track_count),
+            ];
+            let summary = summary.into_iter().flatten().collect::<Vec<_>>().join(" · ");
+
+            let mut lines = vec![
+                line(&card_data.name, fields.name, LineRole::Main),
+                line(&summary, true, LineRole::Details),
+            ];
+            lines.extend(tracklist);
+            lines.push(line(&card_data.genres, fields.genres, LineRole::Footer));
+            lines
+        }
+        CardKind::Artist => {
+            let mut lines = vec![
+                line(&card_data.name, fields.name, LineRole::Main),
+                line(followers.as_deref().unwrap_or_default(), fields.followers, LineRole::Details),
+            ];
+            lines.extend(tracklist);
+            lines.push(line(&card_data.genres, fields.genres, LineRole::Footer));
+            lines
+        }
+        CardKind::Episode => {
+            let summary = [
+                card_data.release_date.as_deref().filter(|_| fields.release_year).map(format_date),
+                card_data.duration_ms.filter(|_| fields.duration).map(format_duration),
+            ];
+            let summary = summary.into_iter().flatten().collect::<Vec<_>>().join(" · ");
+
+            vec![
+                line(&card_data.name, fields.name, LineRole::Main),
+                line(&card_data.album, fields.album, LineRole::Main),
+                line(&card_data.artists, fields.artists, LineRole::Details),
+                line(&summary, true, LineRole::Details),
+                line(&card_data.genres, fields.genres, LineRole::Footer),
+            ]
+        }
+        CardKind::Show => {
+            let episodes = card_data.total_tracks.map(|total| match total {
+                1 => String::from("1 episode"),
+                total => format!("{} episodes", format_count(total)),
+            });
+
+            vec![
+                line(&card_data.name, fields.name, LineRole::Main),
+                line(&card_data.artists, fields.artists, LineRole::Details),
+                line(
+                    episodes.as_deref().unwrap_or_default(),
+                    fields.track_count,
+                    LineRole::Details,
+                ),
+                line(&card_data.genres, fields.genres, LineRole::Footer),
+            ]
+        }
+    }
+}
+
+/// Formats a release date as precisely as it is known, e.g. `17 Oct 2023`, `Oct 2023` or `2023`.
+fn format_date(date: &str) -> String {
+    const MONTHS: [&str; 12] =
+        ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next().unwrap_or_default();
+    let month = parts.next().and_then(|month| month.parse::<usize>().ok());
+    let day = parts.next().and_then(|day| day.parse::<u8>().ok());
+    match month.and_then(|month| MONTHS.get(month.wrapping_sub(1))) {
+        Some(month) => match day {
+            Some(day) => format!("{day} {month} {year}"),
+            None => format!("{month} {year}"),
+        },
+        None => year.to_owned(),
+    }
+}
+
+/// Cuts `text` down to `max_chars` characters, ellipsis included.
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_owned();
+    }
+    let cut: String = text.chars().take(max_chars - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Formats a count with thousands separators, e.g. `12,345`.
+fn format_count(count: u32) -> String {
+    let digits: Vec<char> = count.to_string().chars().collect();
+    digits.rchunks(3).rev().map(String::from_iter).collect::<Vec<_>>().join(",")
+}
+
+/// Formats the duration of a track like players do, e.g. `3:42` or `1:02:05`.
+fn format_time(duration_ms: u64) -> String {
+    let seconds = duration_ms / 1000;
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, minutes, seconds) => format!("{minutes}:{seconds:02}"),
+        (hours, minutes, seconds) => format!("{hours}:{minutes:02}:{seconds:02}"),
+    }
+}
+
+/// Formats a long duration in minutes, e.g. `41 min` or `1 h 12 min`.
+fn format_duration(duration_ms: u64) -> String {
+    let minutes = (duration_ms as f64 / 60_000.0).round() as u64;
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes} min"),
+        (hours, 0) => format!("{hours} h"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
+
+/// WCAG relative luminance of `color`, from 0.0 for black to 1.0 for white.
+pub fn luminance(color: &Rgba<u8>) -> f32 {
     let srgb_to_rgb = |val| {
         let val = val as f32 / 255.0;
         (val <= 0.04045).then_some(val / 12.92).unwrap_or(((val + 0.055) / 1.055).powf(2.4))
@@ -117,7 +737,9 @@ fn luminance(color: &Rgba<u8>) -> f32 {
     0.2126 * srgb_to_rgb(color[0]) + 0.7152 * srgb_to_rgb(color[1]) + 0.0722 * srgb_to_rgb(color[2])
 }
 
-fn generate_text_box(text: &str, font: &Font<'static>, scale: Scale, text_color: Rgba<u8>, is_genres: bool) -> DynamicImage {
+fn generate_text_box(
+    text: &str, font: &Font<'static>, scale: Scale, style: TextStyle, role: LineRole,
+) -> Result<DynamicImage, CardError> {
 	let v_metrics = font.v_metrics(scale);
 	let glyphs_height = (v_metrics.ascent - v_metrics.descent).ceil();
 	let height = if glyphs_height > 12.0 { (glyphs_height * 0.8).round() } else { glyphs_height };
@@ -128,99 +750,164 @@ fn generate_text_box(text: &str, font: &Font<'static>, scale: Scale, text_color:
 	} else {
 		-8
 	};
-	let lines = textwrap::wrap(text, if is_genres { 10000 } else { 36 });
+	let lines = textwrap::wrap(text, role.wrap_width());
 	let line_values = lines.iter().map(|line| {
-		let glyphs: Vec<_> = font.layout(&line, scale, point(20.0, 20.0 + v_metrics.ascent)).collect();
-		
+		let bounding_boxes: Vec<_> = font
+			.layout(line, scale, point(20.0, 20.0 + v_metrics.ascent))
+			.filter_map(|g| g.pixel_bounding_box())
+			.collect();
+
 		let glyphs_width = {
-			let min_x = glyphs.first().map(|g| g.pixel_bounding_box().unwrap().min.x).unwrap();
-			let max_x = glyphs.last().map(|g| g.pixel_bounding_box().unwrap().max.x).unwrap();
-			(max_x - min_x) as u32
+			let min_x = bounding_boxes.first().map(|b| b.min.x);
+			let max_x = bounding_boxes.last().map(|b| b.max.x);
+			min_x.zip(max_x).map(|(min_x, max_x)| (max_x - min_x) as u32)
 		};
-		(glyphs_width, line)
-	}).collect::<Vec<_>>();
+		glyphs_width.map(|width| (width, line)).ok_or_else(|| CardError::EmptyText(text.to_owned()))
+	}).collect::<Result<Vec<_>, _>>()?;
 
 	let total_height = line_values.len() as f32 * (height.ceil() + 4.0);
-	let glyphs_width = line_values.iter().max_by(|a, b| a.0.cmp(&b.0)).unwrap().0;
+	let glyphs_width = line_values
+		.iter()
+		.map(|val| val.0)
+		.max()
+		.ok_or_else(|| CardError::EmptyText(text.to_owned()))?;
 	let mut final_text_box = DynamicImage::new_rgba8(glyphs_width + 6, total_height.ceil() as u32);
 	let mut final_text_box_cursor = 0;
+	let effect_size = if height < 11.0 { 1 } else { 2 };
+	let back_color = style.contrasting_color();
 	line_values.iter().for_each(|val| {
 		let mut text_box = DynamicImage::new_rgba8(val.0 + 6, height.ceil() as u32 + 4);
-		if text_color == TRANSPARENT { vertical_gradient(&mut text_box, &BLACK, &BLACK); }
-		draw_text_mut(&mut text_box, text_color, 2, y_pos, scale, font, &val.1);
+		let mut draw = |color, dx: i32, dy: i32| {
+			draw_text_mut(&mut text_box, color, 2 + dx, y_pos + dy, scale, font, val.1)
+		};
+		match style.fallback {
+			Some(ContrastFallback::Outline) => OUTLINE_OFFSETS
+				.iter()
+				.for_each(|(dx, dy)| draw(back_color, dx * effect_size, dy * effect_size)),
+			Some(ContrastFallback::Shadow) => draw(back_color, effect_size, effect_size),
+			Some(ContrastFallback::Backdrop) | None => {}
+		}
+		draw(style.color, 0, 0);
+		if style.fallback == Some(ContrastFallback::Backdrop) {
+			let mut backdrop = DynamicImage::new_rgba8(text_box.width(), text_box.height());
+			vertical_gradient(&mut backdrop, &back_color, &back_color);
+			overlay(&mut backdrop, &text_box, 0, 0);
+			text_box = backdrop;
+		}
 		overlay(&mut final_text_box, &text_box, 0, final_text_box_cursor as i64);
 		final_text_box_cursor += text_box.height() as i32;
 	});
 
-    final_text_box
+    Ok(final_text_box)
 }
 
-fn get_kmeans_colors(data: &[u8]) -> Vec<CentroidData<Lab>> {
-    let lab: Vec<Lab> = palette::cast::from_component_slice::<Srgb<u8>>(data)
-        .iter()
-        .map(|x| x.into_format().into_color())
+/// Draws `text` cut out of a box of `color`, so the background shows through its letters.
+fn generate_badge(
+    text: &str, font: &Font<'static>, scale: Scale, color: Rgba<u8>,
+) -> Result<DynamicImage, CardError> {
+    let height = (scale.y * BADGE_SIZE).round() as u32;
+    let text_scale = Scale::uniform(scale.y * BADGE_SIZE * BADGE_TEXT_SIZE);
+    let ascent = font.v_metrics(text_scale).ascent;
+    let bounding_boxes: Vec<_> = font
+        .layout(text, text_scale, point(0.0, ascent))
+        .filter_map(|g| g.pixel_bounding_box())
         .collect();
+    let min = bounding_boxes.iter().map(|b| b.min).reduce(|a, b| point(a.x.min(b.x), a.y.min(b.y)));
+    let max = bounding_boxes.iter().map(|b| b.max).reduce(|a, b| point(a.x.max(b.x), a.y.max(b.y)));
+    let (min, max) = min.zip(max).ok_or_else(|| CardError::EmptyText(text.to_owned()))?;
 
-    let mut res = kmeans_colors::Kmeans::new();
-    for i in 0..8 {
-        let run_res = kmeans_colors::get_kmeans_hamerly(9, 20, 5.0, false, &lab, 0 + i as u64);
-        (run_res.score < res.score).then(|| res = run_res);
-    }
+    let (glyphs_width, glyphs_height) = ((max.x - min.x) as u32, (max.y - min.y) as u32);
+    let width = height.max(glyphs_width + height / 2);
+    let mut letters = GrayImage::new(width, height);
+    let x_pos = (width - glyphs_width) as i32 / 2 - min.x;
+    let y_pos = height.saturating_sub(glyphs_height) as i32 / 2 - min.y;
+    draw_text_mut(&mut letters, Luma([255]), x_pos, y_pos, text_scale, font, text);
 
-    Lab::sort_indexed_colors(&res.centroids, &res.indices)
+    let badge = RgbaImage::from_fn(width, height, |x, y| {
+        let coverage = letters.get_pixel(x, y)[0] as u32;
+        let alpha = color[3] as u32 * (255 - coverage) / 255;
+        Rgba([color[0], color[1], color[2], alpha as u8])
+    });
+    Ok(DynamicImage::ImageRgba8(badge))
 }
 
-fn find_best_colors(image: &[u8]) -> GradientColors {
-    let color_filter = |x: &CentroidData<Lab>, bright_limit, dark_limit| {
+/// Converts the pixels of a jacket of any color type to 8 bit sRGB, leaving out the mostly
+/// transparent ones so they cannot tint the palette.
+pub fn jacket_pixels(jacket: &DynamicImage) -> Vec<Srgb<u8>> {
+    jacket
+        .to_rgba8()
+        .pixels()
+        .filter(|pixel| pixel[3] >= 128)
+        .map(|pixel| Srgb::new(pixel[0], pixel[1], pixel[2]))
+        .collect()
+}
+
+/// Picks the plain, gradient and color picker colors of a card among the palette `extractor`
+/// finds in `pixels`.
+pub fn find_best_colors(
+    pixels: &[Srgb<u8>], extractor: &dyn PaletteExtractor, options: &PaletteOptions,
+) -> Result<GradientColors, CardError> {
+    let color_filter = |x: &CentroidData<Lab>, range: BrightnessRange| {
         let c: Srgb = x.centroid.into_color();
         let color = c.into_components();
-        let filter = ((color.0 + color.1 + color.2) < bright_limit)
-            && ((color.0 + color.1 + color.2) > dark_limit);
-        filter.then_some(c).or(None)
+        range.contains(color.0 + color.1 + color.2).then_some(c)
     };
 
     let palette_to_rgb_pixel = |rgb: &Rgb| {
         Rgba([(rgb.red * 255.0) as u8, (rgb.green * 255.0) as u8, (rgb.blue * 255.0) as u8, 255])
     };
 
-    let mut res: Vec<CentroidData<Lab>> = get_kmeans_colors(image);
+    if !(1..=MAX_PALETTE_COLORS).contains(&options.colors) {
+        return Err(CardError::InvalidPaletteSize(options.colors));
+    }
+    // Fully transparent jackets leave no pixels, which k-means cannot cluster.
+    if pixels.is_empty() {
+        return Err(CardError::EmptyPalette);
+    }
+    let mut res: Vec<CentroidData<Lab>> = extractor.extract(pixels, options);
+    let swatches = Swatches::from_centroids(&res);
 
     let gradient = {
-        let mut dominant_colors =
-            res.iter().filter_map(|x| color_filter(x, 2.9, 0.7)).collect::<Vec<Rgb>>();
+        let mut dominant_colors = res
+            .iter()
+            .filter_map(|x| color_filter(x, options.gradient_brightness))
+            .collect::<Vec<Rgb>>();
         dominant_colors.dedup();
-        (!dominant_colors.is_empty() || dominant_colors.len() > 1)
-            .then(|| {
-                let brightest =
-                    palette_to_rgb_pixel(dominant_colors.get(dominant_colors.len() - 2).unwrap());
-                let darkest = palette_to_rgb_pixel(dominant_colors.first().unwrap());
-
-                (brightest, darkest)
-            })
-            .or(None)
+        let brightest = dominant_colors.get(dominant_colors.len().saturating_sub(2));
+        let darkest = dominant_colors.first();
+        brightest.zip(darkest).map(|(brightest, darkest)| {
+            (palette_to_rgb_pixel(brightest), palette_to_rgb_pixel(darkest))
+        })
     };
 
-    res.sort_unstable_by(|a, b| (b.percentage).partial_cmp(&a.percentage).unwrap());
-    let plain = res.iter().filter_map(|x| color_filter(x, 2.1, 0.8)).collect::<Vec<Rgb>>();
+    res.sort_unstable_by(|a, b| b.percentage.total_cmp(&a.percentage));
+    let plain =
+        res.iter().filter_map(|x| color_filter(x, options.plain_brightness)).collect::<Vec<Rgb>>();
 
     let all_colors = {
-        let mut color_list =
-            res.iter().filter_map(|x| color_filter(x, 2.9, 0.7)).collect::<Vec<Rgb>>();
+        let mut color_list = res
+            .iter()
+            .filter_map(|x| color_filter(x, options.gradient_brightness))
+            .collect::<Vec<Rgb>>();
         color_list.dedup();
         color_list.iter().map(|x| palette_to_rgb_pixel(x)).collect()
     };
 
-    GradientColors {
-        plain: palette_to_rgb_pixel(plain.first().unwrap()),
+    Ok(GradientColors {
+        plain: palette_to_rgb_pixel(plain.first().ok_or(CardError::EmptyPalette)?),
         gradient,
         custom_gradient: gradient,
+        custom_middle: None,
         all_colors,
-    }
+        swatches,
+    })
 }
 
-pub fn generate_text_assets(card_data: CardData, canvas_assets: CanvasAssets) -> TextAssets {
-    let font = Font::try_from_bytes(DEFAULT_FONT).expect("Font not found");
-    let jp_font = Font::try_from_bytes(JAPANESE_FONT).expect("Font not found");
+pub fn generate_text_assets(
+    card_data: CardData, canvas_assets: CanvasAssets, spec: &CardSpec,
+) -> Result<TextAssets, CardError> {
+    let font = Font::try_from_bytes(font_bytes(spec.fonts.default)).ok_or(CardError::Font)?;
+    let jp_font = Font::try_from_bytes(font_bytes(spec.fonts.japanese)).ok_or(CardError::Font)?;
     let regex = regex::Regex::new(REGEX_JA).unwrap();
 
     let select_font = |s: &str| regex.is_match(s).then_some(&jp_font).unwrap_or(&font);
@@ -234,87 +921,133 @@ pub fn generate_text_assets(card_data: CardData, canvas_assets: CanvasAssets) ->
             .unwrap_or(scale)
     };
 
-    let texts = vec![&card_data.name, &card_data.album, &card_data.artists, &card_data.genres];
-
-    let mut scales: Vec<Scale> = Vec::new();
-    for i in 0..4 {		
-		let lines_scales = textwrap::wrap(texts[i], 36).iter().map(|line| {
-			adjust_text(
-				line,
-				(i == 3).then_some(Scale::uniform(GENRES_SCALE)).unwrap_or(Scale::uniform(TEXT_SCALE))
-			)
-		}).collect::<Vec<_>>();
-		scales.push(*lines_scales.iter().min_by(|a, b| (a.x).partial_cmp(&b.x).unwrap()).unwrap());
-    }
+    let layout = &canvas_assets.layout;
+    let scales = card_lines(&card_data, &spec.fields, layout)
+        .iter()
+        .map(|line| {
+            let scale = line.role.scale(layout);
+            textwrap::wrap(&line.text, line.role.wrap_width())
+                .iter()
+                .map(|wrapped| adjust_text(wrapped, scale))
+                .min_by(|a, b| a.x.total_cmp(&b.x))
+                .unwrap_or(Scale::uniform(layout.text_scale))
+        })
+        .collect();
 
-    TextAssets {
+    Ok(TextAssets {
         scales,
         font,
         jp_font,
         regex,
-    }
+    })
 }
 
-pub fn generate_canvas_assets(card_data: CardData) -> CanvasAssets {
-    let jacket = image::load_from_memory(&card_data.jacket_bytes).unwrap();
-    let jacket_size = (card_data.jacket_size as f32 * 0.75) as u32;
-    let resized_jacket = jacket.resize(jacket_size, jacket_size, FilterType::Triangle);
+pub fn generate_canvas_assets(
+    card_data: CardData, spec: &CardSpec,
+) -> Result<CanvasAssets, CardError> {
+    let jacket =
+        image::load_from_memory(&card_data.jacket_bytes).map_err(CardError::JacketDecode)?;
+    let jacket_size = (card_data.jacket_size as f32 * spec.layout.jacket_scale) as u32;
+    // Cropped to a square, artist photos are not always one.
+    let resized_jacket = jacket.resize_to_fill(jacket_size, jacket_size, FilterType::Triangle);
 
-    let colors = find_best_colors(jacket.as_bytes());
+    let thumbnail = spec
+        .palette
+        .sample_size
+        .filter(|size| *size < jacket.width().max(jacket.height()))
+        .map(|size| jacket.thumbnail(size, size));
+    let sample = thumbnail.as_ref().unwrap_or(&jacket);
 
-    CanvasAssets { jacket_size, jacket: resized_jacket, colors }
+    let colors = find_best_colors(
+        &jacket_pixels(sample),
+        spec.palette.algorithm.extractor(),
+        &spec.palette,
+    )?;
+
+    Ok(CanvasAssets { jacket_size, jacket: resized_jacket, colors, layout: spec.layout.clone() })
 }
 
-pub fn generate_card(
-    card_data: CardData, canvas_assets: CanvasAssets, text_assets: TextAssets, bg_type: String,
-) -> Vec<u8> {
-    let mut canvas =
-        DynamicImage::new_rgba8(canvas_assets.canvas_width(), canvas_assets.canvas_height());
-    let is_custom = bg_type == "custom";
-
-    match bg_type.as_str() {
-        "plain" => {
-            vertical_gradient(&mut canvas, &canvas_assets.colors.plain, &canvas_assets.colors.plain)
-        }
-        "inverted" => vertical_gradient(
-            &mut canvas,
-            &canvas_assets.colors.gradient_end(false),
-            &canvas_assets.colors.gradient_start(false),
-        ),
-        _ => vertical_gradient(
-            &mut canvas,
-            &canvas_assets.colors.gradient_start(is_custom),
-            &canvas_assets.colors.gradient_end(is_custom),
-        ),
+/// Tiles album covers into a square jacket `size` pixels wide, 3x3 when there are at least 9
+/// covers and 2x2 when there are at least 4. `None` when there are fewer covers than that.
+pub fn mosaic_jacket(covers: &[Vec<u8>], size: u32) -> Result<Option<Vec<u8>>, CardError> {
+    let grid = match covers.len() {
+        9.. => 3,
+        4.. => 2,
+        _ => return Ok(None),
     };
+    // Tiles share the remainder of the division, so the mosaic is exactly `size` wide.
+    let edge = |i: u32| i * size / grid;
+    let mut mosaic = DynamicImage::new_rgba8(size, size);
+    for (i, cover) in covers.iter().take((grid * grid) as usize).enumerate() {
+        let (column, row) = (i as u32 % grid, i as u32 / grid);
+        let (x, y) = (edge(column), edge(row));
+        let tile = image::load_from_memory(cover).map_err(CardError::JacketDecode)?.resize_to_fill(
+            edge(column + 1) - x,
+            edge(row + 1) - y,
+            FilterType::Triangle,
+        );
+        overlay(&mut mosaic, &tile, x as i64, y as i64);
+    }
 
-    overlay(&mut canvas, &canvas_assets.jacket, JACKET_OFFSET as i64, JACKET_OFFSET as i64);
+    let mut buffer: Vec<u8> = vec![];
+    mosaic
+        .write_to(&mut Cursor::new(&mut buffer), image::ImageOutputFormat::Png)
+        .map_err(CardError::Encode)?;
+    Ok(Some(buffer))
+}
 
-    let genres_y_pos = (canvas_assets.canvas_height() - JACKET_OFFSET - 15) as i32;
+/// Renders a card in one go, computing every intermediate asset from `spec` once it is
+/// validated.
+pub fn generate_card_from_spec(card_data: CardData, spec: &CardSpec) -> Result<Vec<u8>, CardError> {
+    spec.validate()?;
+    let canvas_assets = generate_canvas_assets(card_data.clone(), spec)?;
+    let text_assets = generate_text_assets(card_data.clone(), canvas_assets.clone(), spec)?;
+    generate_card(card_data, canvas_assets, text_assets, spec)
+}
 
-    let text_color = |s: &str, is_genres: bool| {
-        let avg_luminance = is_genres
-            .then_some(match bg_type.as_str() {
-                "plain" => luminance(&canvas_assets.colors.plain),
-                "inverted" => luminance(&canvas_assets.colors.gradient_start(false)),
-                _ => luminance(&canvas_assets.colors.gradient_end(is_custom)),
+pub fn generate_card(
+    card_data: CardData, canvas_assets: CanvasAssets, text_assets: TextAssets, spec: &CardSpec,
+) -> Result<Vec<u8>, CardError> {
+    let jacket_offset = canvas_assets.layout.jacket_offset;
+    let (width, height) = (canvas_assets.canvas_width(), canvas_assets.canvas_height());
+
+    let custom_stops = spec.colors.custom_stops.as_ref().filter(|stops| !stops.is_empty());
+    let stops = match (&spec.background, custom_stops) {
+        (Background::Custom, Some(stops)) => stops
+            .iter()
+            .map(|ColorStop { color, position }| {
+                Ok(GradientStop { color: parse_hex_color(color)?, position: *position })
             })
-            .unwrap_or(match bg_type.as_str() {
-                "plain" => luminance(&canvas_assets.colors.plain),
-                _ => luminance(&canvas_assets.colors.gradient_blend(is_custom)),
-            });
-        (avg_luminance > 0.179)
-            .then_some((s.len() == 1).then_some(BLACK).unwrap_or(TRANSPARENT))
-            .unwrap_or(WHITE)
+            .collect::<Result<Vec<_>, CardError>>()?,
+        (background, _) => canvas_assets.colors.background_stops(background)?,
+    };
+
+    let mut canvas = match spec.background {
+        Background::BlurredJacket => {
+            blurred_jacket_background(&canvas_assets.jacket, width, height, spec, &stops)
+        }
+        Background::Plain
+        | Background::Gradient
+        | Background::Inverted
+        | Background::Custom
+        | Background::Solid(_) => DynamicImage::ImageRgba8(gradient_image(
+            width,
+            height,
+            &spec.gradient,
+            &stops,
+            spec.dithering,
+        )),
     };
 
-    let texts = vec![&card_data.name, &card_data.album, &card_data.artists, &card_data.genres];
+    overlay(&mut canvas, &canvas_assets.jacket, jacket_offset as i64, jacket_offset as i64);
+
+    let genres_y_pos =
+        (canvas_assets.canvas_height() - jacket_offset).saturating_sub(GENRES_MARGIN) as i32;
 
     let select_font = |s: &str| {
         text_assets.regex.is_match(s).then_some(&text_assets.jp_font).unwrap_or(&text_assets.font)
     };
 
-    let color_by_idx = |i: usize| text_color(texts[i], i == 3);
     let text_box_offset = |text: &DynamicImage, cursor: i64| {
         if text.height() < 11 {
             cursor
@@ -324,44 +1057,83 @@ pub fn generate_card(
             cursor + 8
         }
     };
-    let y_offset_by_idx = |i: usize, text: &DynamicImage, cursor: i64| {
-        (i == 3).then_some(genres_y_pos as i64).unwrap_or(text_box_offset(text, cursor))
-    };
 
-	let mut y_pos_cursor = TEXT_OFFSET_Y as i64;
-    for i in 0..4 {
-		if texts[i] != "" {
-			if i == 3 && color_by_idx(i) != TRANSPARENT {
-				draw_text_mut(
-					&mut canvas,
-					color_by_idx(i),
-					canvas_assets.text_offset_x() as i32,
-					genres_y_pos,
-					text_assets.scales[i],
-					select_font(texts[i]),
-					texts[i],
-				);		
-				continue;
-			}
-			
-			let text_box = generate_text_box(texts[i], select_font(texts[i]), text_assets.scales[i], color_by_idx(i), i == 3);
-
-			if i == 1 && &card_data.album_type == "single" {
-				y_pos_cursor += (text_box.height() + TEXT_SPACING) as i64;
-				continue;
-			}
-			
-			overlay(
-				&mut canvas,
-				&text_box,
-				canvas_assets.text_offset_x() as i64 - 2,
-				y_offset_by_idx(i, &text_box, y_pos_cursor),
-			);
-			y_pos_cursor += (text_box.height() + TEXT_SPACING) as i64;
-		}
-	}
+    let mut y_pos_cursor = canvas_assets.layout.text_offset_y as i64;
+    let lines = card_lines(&card_data, &spec.fields, &canvas_assets.layout);
+    for (line, scale) in lines.iter().zip(&text_assets.scales) {
+        if line.text.is_empty() && line.badge.is_none() {
+            continue;
+        }
+        let is_footer = line.role == LineRole::Footer;
+        let x_pos = canvas_assets.text_offset_x() as i64 - 2;
+        // Where the badge goes, after the text if there is any.
+        let mut text_area = None;
+        if !line.text.is_empty() {
+            let font = select_font(&line.text);
+            let plain_style = TextStyle { color: WHITE, fallback: None };
+            let measured_box =
+                generate_text_box(&line.text, font, *scale, plain_style, line.role)?;
+            let y_pos = if is_footer {
+                genres_y_pos as i64
+            } else {
+                text_box_offset(&measured_box, y_pos_cursor)
+            };
+            let style = text_style(
+                &region_luminances(
+                    &canvas,
+                    x_pos,
+                    y_pos,
+                    measured_box.width(),
+                    measured_box.height(),
+                ),
+                &spec.contrast,
+            );
+
+            let text_box = if style == plain_style {
+                measured_box
+            } else {
+                generate_text_box(&line.text, font, *scale, style, line.role)?
+            };
+
+            if !line.hidden {
+                overlay(&mut canvas, &text_box, x_pos, y_pos);
+            }
+            text_area = Some((text_box.width(), y_pos, text_box.height()));
+        }
+
+        let mut line_height = text_area.map_or(0, |(_, _, height)| height);
+        if let Some(badge) = line.badge {
+            let measured_badge = generate_badge(badge, &text_assets.font, *scale, WHITE)?;
+            let (badge_x_pos, badge_y_pos) = match text_area {
+                Some((width, y_pos, height)) => (
+                    x_pos + (width + BADGE_GAP) as i64,
+                    y_pos + (height as i64 - measured_badge.height() as i64) / 2,
+                ),
+                None => (x_pos + 2, text_box_offset(&measured_badge, y_pos_cursor)),
+            };
+            let style = text_style(
+                &region_luminances(
+                    &canvas,
+                    badge_x_pos,
+                    badge_y_pos,
+                    measured_badge.width(),
+                    measured_badge.height(),
+                ),
+                &spec.contrast,
+            );
+            let badge_box = generate_badge(badge, &text_assets.font, *scale, style.color)?;
+
+            if !line.hidden {
+                overlay(&mut canvas, &badge_box, badge_x_pos, badge_y_pos);
+            }
+            line_height = line_height.max(badge_box.height());
+        }
+        y_pos_cursor += (line_height + line.role.spacing(&canvas_assets.layout)) as i64;
+    }
 
     let mut buffer: Vec<u8> = vec![];
-    canvas.write_to(&mut Cursor::new(&mut buffer), image::ImageOutputFormat::Png).unwrap();
-    buffer
+    canvas
+        .write_to(&mut Cursor::new(&mut buffer), image::ImageOutputFormat::Png)
+        .map_err(CardError::Encode)?;
+    Ok(buffer)
 }
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use image::Rgba;
+use kmeans_colors::{CentroidData, Sort};
+use palette::{Hsl, IntoColor, Lab, Srgb};
+
+use crate::model::{PaletteAlgorithm, PaletteOptions};
+
+/// Reduces the pixels of a jacket to a handful of representative colors.
+pub trait PaletteExtractor {
+    /// Returns at most `options.colors` colors with the share of pixels they stand for, sorted
+    /// from darkest to brightest. Centroids are indexed with a `u8`, so `options.colors` must be
+    /// from 1 to 256, which [`find_best_colors`](crate::cards::find_best_colors) checks.
+    fn extract(&self, pixels: &[Srgb<u8>], options: &PaletteOptions) -> Vec<CentroidData<Lab>>;
+}
+
+/// Named colors picked among the palette of a jacket, following Android's `Palette`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Swatches {
+    pub vibrant: Option<Rgba<u8>>,
+    pub light_vibrant: Option<Rgba<u8>>,
+    pub dark_vibrant: Option<Rgba<u8>>,
+    pub muted: Option<Rgba<u8>>,
+    pub light_muted: Option<Rgba<u8>>,
+    pub dark_muted: Option<Rgba<u8>>,
+}
+
+/// Minimum, target and maximum saturation and lightness of a swatch, all from 0.0 to 1.0.
+struct SwatchTarget {
+    saturation: (f32, f32, f32),
+    lightness: (f32, f32, f32),
+}
+
+const VIBRANT_SATURATION: (f32, f32, f32) = (0.35, 1.0, 1.0);
+const MUTED_SATURATION: (f32, f32, f32) = (0.0, 0.3, 0.4);
+const LIGHT_LIGHTNESS: (f32, f32, f32) = (0.55, 0.74, 1.0);
+const NORMAL_LIGHTNESS: (f32, f32, f32) = (0.3, 0.5, 0.7);
+const DARK_LIGHTNESS: (f32, f32, f32) = (0.0, 0.26, 0.45);
+
+/// Targets in the order the swatches are picked.
+const SWATCH_TARGETS: [SwatchTarget; 6] = [
+    SwatchTarget { saturation: VIBRANT_SATURATION, lightness: NORMAL_LIGHTNESS },
+    SwatchTarget { saturation: VIBRANT_SATURATION, lightness: LIGHT_LIGHTNESS },
+    SwatchTarget { saturation: VIBRANT_SATURATION, lightness: DARK_LIGHTNESS },
+    SwatchTarget { saturation: MUTED_SATURATION, lightness: NORMAL_LIGHTNESS },
+    SwatchTarget { saturation: MUTED_SATURATION, lightness: LIGHT_LIGHTNESS },
+    SwatchTarget { saturation: MUTED_SATURATION, lightness: DARK_LIGHTNESS },
+];
+
+impl Swatches {
+    /// Scores every centroid against each target by closeness in saturation and lightness and
+    /// by population, never using the same centroid for two swatches.
+    pub fn from_centroids(centroids: &[CentroidData<Lab>]) -> Self {
+        let colors = centroids
+            .iter()
+            .map(|c| {
+                let srgb: Srgb = c.centroid.into_color();
+                let hsl: Hsl = srgb.into_color();
+                let rgb = srgb.into_format::<u8>();
+                (Rgba([rgb.red, rgb.green, rgb.blue, 255]), hsl, c.percentage)
+            })
+            .collect::<Vec<_>>();
+        let max_population = colors.iter().map(|c| c.2).fold(0.0, f32::max).max(f32::EPSILON);
+        let in_range = |value: f32, (min, _, max): (f32, f32, f32)| value >= min && value <= max;
+        let closeness = |value: f32, (_, target, _): (f32, f32, f32)| 1.0 - (value - target).abs();
+
+        let mut used = vec![false; colors.len()];
+        let mut picked = SWATCH_TARGETS.iter().map(|target| {
+            let best = colors
+                .iter()
+                .enumerate()
+                .filter(|(i, (_, hsl, _))| {
+                    !used[*i]
+                        && in_range(hsl.saturation, target.saturation)
+                        && in_range(hsl.lightness, target.lightness)
+                })
+                .map(|(i, (color, hsl, population))| {
+                    let score = closeness(hsl.saturation, target.saturation) * 0.24
+                        + closeness(hsl.lightness, target.lightness) * 0.52
+                        + population / max_population * 0.24;
+                    (i, *color, score)
+                })
+                .max_by(|a, b| a.2.total_cmp(&b.2));
+            best.map(|(i, color, _)| {
+                used[i] = true;
+                color
+            })
+        });
+
+        Swatches {
+            vibrant: picked.next().flatten(),
+            light_vibrant: picked.next().flatten(),
+            dark_vibrant: picked.next().flatten(),
+            muted: picked.next().flatten(),
+            light_muted: picked.next().flatten(),
+            dark_muted: picked.next().flatten(),
+        }
+    }
+
+    /// Named start and end colors of the gradients that can be built from these swatches, all six
+    /// of them being used. Presets missing either color are left out, so "Light" and "Dark" keep
+    /// some presets for jackets without light or without dark swatches.
+    pub fn gradient_presets(&self) -> Vec<(&'static str, Rgba<u8>, Rgba<u8>)> {
+        [
+            ("Vibrant", self.light_vibrant, self.dark_vibrant),
+            ("Muted", self.light_muted, self.dark_muted),
+            ("Vibrant to Muted", self.vibrant, self.dark_muted),
+            ("Muted to Vibrant", self.muted, self.dark_vibrant),
+            ("Light", self.light_vibrant, self.light_muted),
+            ("Dark", self.dark_vibrant, self.dark_muted),
+        ]
+        .into_iter()
+        .filter_map(|(name, start, end)| Some((name, start?, end?)))
+        .collect()
+    }
+}
+
+/// Hamerly k-means in Lab space, keeping the best of several differently seeded runs.
+pub struct KMeans;
+
+/// Recursively splits the color box with the widest channel range at its median.
+pub struct MedianCut;
+
+/// Buckets colors in an octree and merges the least populated branches.
+pub struct Octree;
+
+impl PaletteAlgorithm {
+    pub fn extractor(&self) -> &'static dyn PaletteExtractor {
+        match self {
+            PaletteAlgorithm::KMeans => &KMeans,
+            PaletteAlgorithm::MedianCut => &MedianCut,
+            PaletteAlgorithm::Octree => &Octree,
+        }
+    }
+}
+
+/// Turns groups of pixels into centroids, sorted like [`Sort::sort_indexed_colors`] does.
+///
+/// Groups averaging to the same color, like the halves of a single color split by median cut,
+/// are merged into one centroid.
+fn centroids_from_groups(groups: impl Iterator<Item = (u64, [u64; 3])>) -> Vec<CentroidData<Lab>> {
+    let mut means: BTreeMap<[u8; 3], u64> = BTreeMap::new();
+    for (count, sum) in groups.filter(|(count, _)| *count > 0) {
+        *means.entry(sum.map(|s| (s / count) as u8)).or_default() += count;
+    }
+    let total = means.values().sum::<u64>() as f32;
+    // Extractors never make more groups than `options.colors`, which is at most 256.
+    debug_assert!(means.len() <= u8::MAX as usize + 1, "{} groups", means.len());
+
+    let mut centroids = means
+        .iter()
+        .zip(0..=u8::MAX)
+        .map(|((mean, count), index)| CentroidData {
+            centroid: Srgb::from(*mean).into_format::<f32>().into_color(),
+            percentage: *count as f32 / total,
+            index,
+        })
+        .collect::<Vec<CentroidData<Lab>>>();
+    // A stable sort keeps colors of equal lightness in the order of their channels.
+    centroids.sort_by(|a, b| a.centroid.l.total_cmp(&b.centroid.l));
+    centroids
+}
+
+impl PaletteExtractor for KMeans {
+    fn extract(&self, pixels: &[Srgb<u8>], options: &PaletteOptions) -> Vec<CentroidData<Lab>> {
+        let lab: Vec<Lab> = pixels.iter().map(|x| x.into_format().into_color()).collect();
+
+        let mut res = kmeans_colors::Kmeans::new();
+        for seed in 0..options.runs {
+            let run_res = kmeans_colors::get_kmeans_hamerly(
+                options.colors,
+                options.iterations,
+                options.converge,
+                false,
+                &lab,
+                seed,
+            );
+            if run_res.score < res.score {
+                res = run_res;
+            }
+        }
+
+        Lab::sort_indexed_colors(&res.centroids, &res.indices)
+    }
+}
+
+impl PaletteExtractor for MedianCut {
+    fn extract(&self, pixels: &[Srgb<u8>], options: &PaletteOptions) -> Vec<CentroidData<Lab>> {
+        let channels = |p: &Srgb<u8>| [p.red, p.green, p.blue];
+        let widest_channel = |group: &[Srgb<u8>]| {
+            (0..3)
+                .map(|c| {
+                    let values = group.iter().map(|p| channels(p)[c]);
+                    let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                    (c, range)
+                })
+                .max_by_key(|(_, range)| *range)
+                .unwrap_or((0, 0))
+        };
+
+        let mut groups = vec![pixels.to_vec()];
+        while groups.len() < options.colors {
+            let widest = groups
+                .iter()
+                .enumerate()
+                .map(|(i, group)| (i, widest_channel(group)))
+                .filter(|(_, (_, range))| *range > 0)
+                .max_by_key(|(_, (_, range))| *range);
+            let Some((i, (channel, _))) = widest else { break };
+
+            let mut group = groups.swap_remove(i);
+            group.sort_unstable_by_key(|p| channels(p)[channel]);
+            let upper = group.split_off(group.len() / 2);
+            groups.extend([group, upper]);
+        }
+
+        centroids_from_groups(groups.iter().map(|group| {
+            let mut sum = [0u64; 3];
+            group.iter().for_each(|p| {
+                sum.iter_mut().zip(channels(p)).for_each(|(s, c)| *s += c as u64);
+            });
+            (group.len() as u64, sum)
+        }))
+    }
+}
+
+impl PaletteExtractor for Octree {
+    fn extract(&self, pixels: &[Srgb<u8>], options: &PaletteOptions) -> Vec<CentroidData<Lab>> {
+        // Leaves are keyed by their depth and the top `depth` bits of each channel.
+        // Ordered maps keep the merges, and thus the palette, the same from one run to the next.
+        let mut leaves: BTreeMap<(u8, [u8; 3]), (u64, [u64; 3])> = BTreeMap::new();
+        for p in pixels {
+            let leaf = leaves.entry((8, [p.red, p.green, p.blue])).or_default();
+            leaf.0 += 1;
+            leaf.1.iter_mut().zip([p.red, p.green, p.blue]).for_each(|(s, c)| *s += c as u64);
+        }
+
+        for depth in (1..=8).rev() {
+            if leaves.len() <= options.colors {
+                break;
+            }
+
+            let mut parents: BTreeMap<[u8; 3], Vec<(u8, [u8; 3])>> = BTreeMap::new();
+            leaves.keys().filter(|(d, _)| *d == depth).for_each(|&(d, key)| {
+                parents.entry(key.map(|c| c >> 1)).or_default().push((d, key));
+            });
+            let mut parents = parents.into_iter().collect::<Vec<_>>();
+            let population =
+                |children: &[(u8, [u8; 3])]| children.iter().map(|c| leaves[c].0).sum::<u64>();
+            parents.sort_by_cached_key(|(key, children)| (population(children), *key));
+
+            for (parent, children) in parents {
+                if leaves.len() <= options.colors {
+                    break;
+                }
+                let mut merged = (0, [0u64; 3]);
+                for child in children {
+                    let (count, sum) = leaves.remove(&child).unwrap_or_default();
+                    merged.0 += count;
+                    merged.1.iter_mut().zip(sum).for_each(|(s, c)| *s += c);
+                }
+                leaves.insert((depth - 1, parent), merged);
+            }
+        }
+
+        centroids_from_groups(leaves.into_values())
+    }
+}
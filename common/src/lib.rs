@@ -1,2 +1,6 @@
 pub mod cards;
+pub mod colors;
 pub mod model;
+pub mod providers;
+#[cfg(feature = "tags")]
+pub mod tags;
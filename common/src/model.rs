@@ -1,5 +1,9 @@
+use std::{fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
+use crate::cards::parse_hex_color;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct AccessToken {
     pub access_token: String,
@@ -19,6 +23,55 @@ pub struct Album {
     pub album_type: String,
     pub images: Vec<Image>,
     pub name: String,
+    pub id: String,
+    #[serde(default)]
+    pub artists: Vec<Artist>,
+    /// `1971`, `1971-12` or `1971-12-17`, depending on what Spotify knows.
+    pub release_date: Option<String>,
+    pub release_date_precision: Option<DatePrecision>,
+    pub total_tracks: Option<u32>,
+    /// Only listed by the album endpoint, like the tracks and popularity.
+    pub label: Option<String>,
+    pub popularity: Option<u8>,
+    #[serde(default)]
+    pub external_urls: ExternalUrls,
+    pub tracks: Option<Page<AlbumTrack>>,
+}
+
+/// Part of a release date Spotify knows.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DatePrecision {
+    Year,
+    Month,
+    Day,
+}
+
+/// Ids of a track in other catalogs.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ExternalIds {
+    pub isrc: Option<String>,
+}
+
+/// Pages of an item on the web.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ExternalUrls {
+    pub spotify: Option<String>,
+}
+
+/// Track listed in an album, without the album itself.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AlbumTrack {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+/// One page of a list, the next one is requested with a greater `offset`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u32,
+    pub next: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -26,6 +79,17 @@ pub struct Artist {
     pub id: String,
     pub name: String,
     pub genres: Option<Vec<String>>,
+    /// Only listed by the artist endpoint, like the images.
+    pub followers: Option<Followers>,
+    /// Photos of the artist, from the largest to the smallest.
+    #[serde(default)]
+    pub images: Vec<Image>,
+}
+
+/// Most popular tracks of an artist.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TopTracks {
+    pub tracks: Vec<Track>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -34,10 +98,101 @@ pub struct Track {
     pub name: String,
     pub album: Album,
     pub artists: Vec<Artist>,
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub explicit: bool,
+    /// From 0 to 100, based on how often and how recently the track was played.
+    pub popularity: Option<u8>,
+    pub disc_number: Option<u32>,
+    pub track_number: Option<u32>,
+    #[serde(default)]
+    pub external_ids: ExternalIds,
+    #[serde(default)]
+    pub external_urls: ExternalUrls,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+/// Podcast on Spotify.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Show {
+    pub id: String,
+    pub name: String,
+    pub publisher: String,
+    pub images: Vec<Image>,
+    pub total_episodes: Option<u32>,
+}
+
+/// Episode of a podcast, with the show it belongs to.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Episode {
+    pub id: String,
+    pub name: String,
+    /// Artwork of the episode, which is usually the artwork of the show.
+    pub images: Vec<Image>,
+    /// `2023`, `2023-10` or `2023-10-17`, depending on what Spotify knows.
+    pub release_date: String,
+    pub duration_ms: u64,
+    pub show: Show,
+}
+
+/// Spotify playlist, with the first page of its tracks.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Playlist {
+    pub id: String,
+    pub name: String,
+    /// Cover uploaded by the owner, or the mosaic Spotify makes itself.
+    pub images: Vec<PlaylistImage>,
+    pub owner: PlaylistOwner,
+    pub followers: Followers,
+    pub tracks: Page<PlaylistItem>,
+}
+
+/// Image whose size Spotify does not always know, like playlist covers.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistImage {
+    pub url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistOwner {
+    pub id: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Followers {
+    pub total: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistItem {
+    /// Missing when the track is no longer available.
+    pub track: Option<PlaylistTrack>,
+}
+
+/// Track of a playlist, which has no ids when it is a local file.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistTrack {
+    pub name: String,
+    #[serde(default)]
+    pub artists: Vec<PlaylistArtist>,
+    pub album: Option<PlaylistAlbum>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistArtist {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistAlbum {
+    pub id: Option<String>,
+    #[serde(default)]
+    pub images: Vec<PlaylistImage>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
 pub struct CardData {
+    /// Id the card was requested with, see [`CardKind`].
     pub track_id: String,
     pub name: String,
     pub album: String,
@@ -46,6 +201,62 @@ pub struct CardData {
     pub genres: String,
     pub jacket_size: u16,
     pub jacket_bytes: Vec<u8>,
+    #[serde(default)]
+    pub kind: CardKind,
+    /// `1971`, `1971-12` or `1971-12-17`, only as precise as the provider knows.
+    pub release_date: Option<String>,
+    /// Tracks of the album, also on track cards.
+    pub total_tracks: Option<u32>,
+    pub duration_ms: Option<u64>,
+    pub label: Option<String>,
+    #[serde(default)]
+    pub explicit: bool,
+    /// From 0 to 100.
+    pub popularity: Option<u8>,
+    pub disc_number: Option<u32>,
+    /// Position of the track on its disc.
+    pub track_number: Option<u32>,
+    pub isrc: Option<String>,
+    /// Page of the card subject on the provider website.
+    pub external_url: Option<String>,
+    /// Name of the user who made the playlist.
+    pub owner: Option<String>,
+    /// Followers of the playlist or artist.
+    pub followers: Option<u32>,
+    /// First tracks of the playlist, or top tracks of the artist, in order.
+    #[serde(default)]
+    pub tracklist: Vec<ListedTrack>,
+}
+
+/// Track listed on a playlist or artist card.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct ListedTrack {
+    pub name: String,
+    /// Every artist joined with ", ", only the featured ones on artist cards.
+    pub artists: String,
+}
+
+/// What a card is about, which decides the lines drawn next to the jacket.
+///
+/// Card ids are prefixed with the kind, e.g. `album:6fQElzBNTiEMGdIeY0hy5l`, except for tracks.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CardKind {
+    /// Track name, album, artists and genres.
+    #[default]
+    Track,
+    /// Album name, artists, release year, track count, duration, label and genres.
+    Album,
+    /// Playlist name, owner, followers, track count and first tracks, next to a mosaic of the
+    /// covers of its albums.
+    Playlist,
+    /// Artist name, followers, top tracks and genres, next to their photo.
+    Artist,
+    /// Podcast episode title, show name as `album`, publisher as `artists`, release date and
+    /// duration.
+    Episode,
+    /// Podcast name, publisher as `artists` and episode count as `total_tracks`.
+    Show,
 }
 
 impl Track {
@@ -54,6 +265,23 @@ impl Track {
     }
 }
 
+impl Album {
+    pub fn artists(&self) -> String {
+        self.artists.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Release date cut to its precision, e.g. `1971` rather than `1971-01-01`.
+    pub fn release_date(&self) -> Option<String> {
+        let date = self.release_date.as_deref()?;
+        let len = match self.release_date_precision {
+            Some(DatePrecision::Year) => 4,
+            Some(DatePrecision::Month) => 7,
+            Some(DatePrecision::Day) | None => date.len(),
+        };
+        Some(date.get(..len).unwrap_or(date).to_owned())
+    }
+}
+
 impl Artist {
     pub fn genres(&self) -> Option<String> {
         if self.genres.is_some() {
@@ -71,3 +299,308 @@ impl Artist {
         }
     }
 }
+
+/// Everything needed to reproduce a render from the same [`CardData`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct CardSpec {
+    pub background: Background,
+    pub gradient: GradientShape,
+    pub dithering: Dithering,
+    pub colors: CardColors,
+    pub contrast: ContrastOptions,
+    pub palette: PaletteOptions,
+    pub fields: CardFields,
+    pub layout: CardLayout,
+    pub fonts: CardFonts,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Background {
+    /// Single color taken from the jacket palette.
+    Plain,
+    /// Vertical gradient between the brightest and darkest jacket colors.
+    #[default]
+    Gradient,
+    /// Same as `Gradient`, from darkest to brightest.
+    Inverted,
+    /// Vertical gradient between two user-picked colors.
+    Custom,
+    /// The jacket itself, enlarged to cover the card, blurred and tinted with the gradient colors.
+    #[serde(rename = "blurred")]
+    BlurredJacket,
+    /// Single `#rrggbb` color unrelated to the jacket.
+    Solid(String),
+}
+
+/// Shape of the gradient drawn by the gradient based backgrounds.
+///
+/// Angles are in degrees and follow CSS: 0 points up, 90 to the right and 180, the default,
+/// goes from top to bottom.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GradientShape {
+    Linear {
+        angle: f32,
+    },
+    /// Circle centered on the card, reaching its farthest corner.
+    Radial,
+    /// Sweep around the center of the card, starting at `angle`.
+    Conic {
+        angle: f32,
+    },
+}
+
+/// Noise added to gradients before quantizing them to 8 bits, hiding banding between close
+/// colors at the cost of a bigger PNG.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Dithering {
+    #[default]
+    None,
+    /// Ordered dithering with an 8x8 Bayer matrix.
+    Bayer,
+}
+
+/// Hex colors (`#rrggbb`) overriding the ones extracted from the jacket.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct CardColors {
+    /// Stops of the custom background, ignored when empty.
+    pub custom_stops: Option<Vec<ColorStop>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ColorStop {
+    pub color: String,
+    /// Position along the gradient, from 0.0 to 1.0.
+    pub position: f32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ContrastOptions {
+    /// Minimum WCAG contrast ratio between a text and the pixels behind it, from 1.0 to 21.0.
+    pub min_ratio: f32,
+    /// What is drawn around a text when neither white nor black reach `min_ratio`.
+    pub fallback: ContrastFallback,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContrastFallback {
+    Outline,
+    Shadow,
+    /// Semi-transparent box behind the text.
+    #[default]
+    Backdrop,
+}
+
+/// How the colors of a card are extracted from its jacket.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PaletteOptions {
+    pub algorithm: PaletteAlgorithm,
+    /// The jacket is shrunk to fit in a square of this size before extracting its colors, or
+    /// used as is when `None`. Smaller samples are much faster and barely change the colors.
+    pub sample_size: Option<u32>,
+    /// Maximum number of colors extracted from the jacket, from 1 to 256.
+    pub colors: usize,
+    /// Iterations of each k-means run.
+    pub iterations: usize,
+    /// Number of k-means runs, each seeded with its index, keeping the best scoring one.
+    pub runs: u64,
+    /// Score change under which a k-means run is considered converged.
+    pub converge: f32,
+    /// Colors usable in gradients and offered in the color picker.
+    pub gradient_brightness: BrightnessRange,
+    /// Colors usable as the plain background, usually narrower than `gradient_brightness`.
+    pub plain_brightness: BrightnessRange,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteAlgorithm {
+    #[default]
+    KMeans,
+    MedianCut,
+    Octree,
+}
+
+/// Exclusive bounds for the sum of the red, green and blue components of a color, from 0.0
+/// (black) to 3.0 (white).
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct BrightnessRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CardFields {
+    pub name: bool,
+    pub album: bool,
+    pub artists: bool,
+    pub genres: bool,
+    pub release_year: bool,
+    pub track_count: bool,
+    pub duration: bool,
+    pub label: bool,
+    pub owner: bool,
+    pub followers: bool,
+    pub tracklist: bool,
+    /// Badge of explicit tracks, off by default.
+    pub explicit: bool,
+    /// Position of a track in its album, e.g. `4/12`, off by default.
+    pub track_number: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CardLayout {
+    /// Fraction of the original jacket size used for the rendered jacket.
+    pub jacket_scale: f32,
+    /// Margin around the jacket, at least 15 pixels for the genres line.
+    pub jacket_offset: u32,
+    /// Width of the text column relative to the rendered jacket size.
+    pub text_area_ratio: f32,
+    pub text_offset_y: u32,
+    pub text_spacing: u32,
+    pub text_scale: f32,
+    pub genres_scale: f32,
+    /// Size of the smaller lines, like the release year and duration of albums.
+    pub details_scale: f32,
+    /// Size of the lines of lists, like the tracks of playlists.
+    pub list_scale: f32,
+    /// Space between the lines of lists, `text_spacing` is used between any other lines.
+    pub list_spacing: u32,
+    /// Number of tracks listed on playlist cards.
+    pub tracklist_length: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CardFonts {
+    pub default: BundledFont,
+    /// Used instead of `default` for texts containing CJK characters.
+    pub japanese: BundledFont,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BundledFont {
+    Montserrat,
+    MPlus2,
+}
+
+impl fmt::Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Background::Plain => write!(f, "plain"),
+            Background::Gradient => write!(f, "gradient"),
+            Background::Inverted => write!(f, "inverted"),
+            Background::Custom => write!(f, "custom"),
+            Background::BlurredJacket => write!(f, "blurred"),
+            Background::Solid(hex) => write!(f, "solid-{}", hex.trim_start_matches('#')),
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Background::Plain),
+            "gradient" => Ok(Background::Gradient),
+            "inverted" => Ok(Background::Inverted),
+            "custom" => Ok(Background::Custom),
+            "blurred" => Ok(Background::BlurredJacket),
+            _ => {
+                let hex =
+                    s.strip_prefix("solid-").ok_or_else(|| format!("Unknown background {s}"))?;
+                let hex = format!("#{}", hex.trim_start_matches('#'));
+                parse_hex_color(&hex).map_err(|err| err.to_string())?;
+                Ok(Background::Solid(hex))
+            }
+        }
+    }
+}
+
+impl Default for GradientShape {
+    fn default() -> Self {
+        GradientShape::Linear { angle: 180.0 }
+    }
+}
+
+impl Default for ContrastOptions {
+    fn default() -> Self {
+        Self { min_ratio: 4.5, fallback: ContrastFallback::default() }
+    }
+}
+
+impl Default for PaletteOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: PaletteAlgorithm::default(),
+            sample_size: Some(128),
+            colors: 9,
+            iterations: 20,
+            runs: 8,
+            converge: 5.0,
+            gradient_brightness: BrightnessRange { min: 0.7, max: 2.9 },
+            plain_brightness: BrightnessRange { min: 0.8, max: 2.1 },
+        }
+    }
+}
+
+impl BrightnessRange {
+    pub fn contains(&self, brightness: f32) -> bool {
+        brightness > self.min && brightness < self.max
+    }
+}
+
+impl Default for CardFields {
+    fn default() -> Self {
+        Self {
+            name: true,
+            album: true,
+            artists: true,
+            genres: true,
+            release_year: true,
+            track_count: true,
+            duration: true,
+            label: true,
+            owner: true,
+            followers: true,
+            tracklist: true,
+            explicit: false,
+            track_number: false,
+        }
+    }
+}
+
+impl Default for CardLayout {
+    fn default() -> Self {
+        Self {
+            jacket_scale: 0.75,
+            jacket_offset: 30,
+            text_area_ratio: 0.84,
+            text_offset_y: 30,
+            text_spacing: 60,
+            text_scale: 60.0,
+            genres_scale: 10.0,
+            details_scale: 28.0,
+            list_scale: 22.0,
+            list_spacing: 8,
+            tracklist_length: 5,
+        }
+    }
+}
+
+impl Default for CardFonts {
+    fn default() -> Self {
+        Self { default: BundledFont::Montserrat, japanese: BundledFont::MPlus2 }
+    }
+}
@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{
+    check, download_jacket, hashtags, request_error, response_error, MusicProvider, ProviderError,
+};
+use crate::model::{CardData, CardKind};
+
+const API_URL: &str = "https://itunes.apple.com";
+const LINK: &str = r"^https?://(?:(?:geo\.)?music|itunes)\.apple\.com/";
+/// Album links point to a track with `?i=`, song links end with the track id.
+const TRACK_ID: &str = r"(?:[?&]i=(\d+)|/song/(?:[^/?#]+/)?(?:id)?(\d+)(?:[?#]|$))";
+/// Album links without `?i=` are about the whole album.
+const ALBUM_ID: &str = r"/album/(?:[^/?#]+/)?(?:id)?(\d+)(?:[?#]|$)";
+/// The lookup API lists 100px jackets, the artwork server scales them to any size in the URL.
+const LISTED_ARTWORK: &str = "/100x100bb.";
+const ARTWORK: &str = "/1000x1000bb.";
+
+#[derive(Deserialize)]
+struct Lookup {
+    results: Vec<LookupResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LookupResult {
+    /// `track` for songs, `collection` or `artist` for the other results.
+    wrapper_type: String,
+    track_id: Option<u64>,
+    track_name: Option<String>,
+    collection_name: Option<String>,
+    artist_name: String,
+    primary_genre_name: Option<String>,
+    artwork_url100: Option<String>,
+    /// Release time, e.g. `2001-03-07T12:00:00Z`.
+    release_date: Option<String>,
+    track_count: Option<u32>,
+    track_time_millis: Option<u64>,
+    collection_view_url: Option<String>,
+}
+
+/// Tracks and albums from the iTunes Search API, which also resolves Apple Music links.
+pub struct AppleMusicProvider {
+    http: Client,
+    api_url: String,
+}
+
+impl Default for AppleMusicProvider {
+    fn default() -> Self {
+        AppleMusicProvider { http: Client::new(), api_url: API_URL.to_owned() }
+    }
+}
+
+impl AppleMusicProvider {
+    pub fn new() -> Self {
+        AppleMusicProvider::default()
+    }
+
+    /// Sends the API requests somewhere else, e.g. to a stub server in tests.
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Looks `id` up along with the related `entity` results, e.g. the songs of an album.
+    async fn lookup(
+        &self, id: &str, entity: Option<&str>, what: &str,
+    ) -> Result<Vec<LookupResult>, ProviderError> {
+        let url = format!("{}/lookup", self.api_url);
+        let mut query = vec![("id", id)];
+        query.extend(entity.map(|entity| ("entity", entity)));
+        let response = self.http.get(&url).query(&query).send().await.map_err(request_error)?;
+        let lookup: Lookup = check(response, what)?.json().await.map_err(response_error)?;
+        Ok(lookup.results)
+    }
+
+    /// Downloads the listed artwork of `what` in a size fit for a jacket, along with its width.
+    async fn jacket(
+        &self, artwork_url: Option<String>, what: &str,
+    ) -> Result<(u16, Vec<u8>), ProviderError> {
+        let artwork = artwork_url
+            .ok_or_else(|| ProviderError::NotFound(format!("jacket of {what}")))?
+            .replace(LISTED_ARTWORK, ARTWORK);
+        download_jacket(&self.http, &artwork).await
+    }
+}
+
+/// Splits the ` - Single` and ` - EP` suffixes Apple adds to collection names into an album type.
+fn album_and_type(collection_name: &str) -> (String, &'static str) {
+    [" - Single", " - EP"]
+        .into_iter()
+        .find_map(|suffix| collection_name.strip_suffix(suffix))
+        .map_or((collection_name.to_owned(), "album"), |album| (album.to_owned(), "single"))
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl MusicProvider for AppleMusicProvider {
+    fn name(&self) -> &'static str {
+        "Apple Music"
+    }
+
+    fn parse_track_id(&self, input: &str) -> Option<String> {
+        let input = input.trim();
+        if !Regex::new(LINK).unwrap().is_match(input) {
+            return None;
+        }
+        if let Some(captures) = Regex::new(TRACK_ID).unwrap().captures(input) {
+            return captures.get(1).or_else(|| captures.get(2)).map(|id| id.as_str().to_owned());
+        }
+        let captures = Regex::new(ALBUM_ID).unwrap().captures(input)?;
+        Some(format!("album:{}", &captures[1]))
+    }
+
+    async fn track(&self, id: &str) -> Result<CardData, ProviderError> {
+        let what = format!("track {id}");
+        let results = self.lookup(id, None, &what).await?;
+        let Some(track) = results.into_iter().find(|result| result.wrapper_type == "track") else {
+            return Err(ProviderError::NotFound(what));
+        };
+
+        let (jacket_size, jacket_bytes) = self.jacket(track.artwork_url100, &what).await?;
+        let (album, album_type) =
+            album_and_type(track.collection_name.as_deref().unwrap_or_default());
+
+        Ok(CardData {
+            track_id: track.track_id.map_or_else(|| id.to_owned(), |id| id.to_string()),
+            name: track.track_name.unwrap_or_default(),
+            album,
+            album_type: album_type.to_owned(),
+            artists: track.artist_name,
+            genres: hashtags(track.primary_genre_name.as_deref()),
+            jacket_size,
+            jacket_bytes,
+            ..Default::default()
+        })
+    }
+
+    async fn album(&self, id: &str) -> Result<CardData, ProviderError> {
+        let what = format!("album {id}");
+        let results = self.lookup(id, Some("song"), &what).await?;
+        let duration_ms = results.iter().filter_map(|result| result.track_time_millis).sum();
+        let Some(collection) =
+            results.into_iter().find(|result| result.wrapper_type == "collection")
+        else {
+            return Err(ProviderError::NotFound(what));
+        };
+
+        let (jacket_size, jacket_bytes) = self.jacket(collection.artwork_url100, &what).await?;
+        let (album, album_type) =
+            album_and_type(collection.collection_name.as_deref().unwrap_or_default());
+
+        Ok(CardData {
+            track_id: format!("album:{id}"),
+            name: album.clone(),
+            album,
+            album_type: album_type.to_owned(),
+            artists: collection.artist_name,
+            genres: hashtags(collection.primary_genre_name.as_deref()),
+            jacket_size,
+            jacket_bytes,
+            kind: CardKind::Album,
+            release_date: collection.release_date.map(|date| date.chars().take(10).collect()),
+            total_tracks: collection.track_count,
+            duration_ms: (duration_ms > 0).then_some(duration_ms),
+            external_url: collection.collection_view_url,
+            ..Default::default()
+        })
+    }
+}
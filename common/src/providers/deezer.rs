@@ -0,0 +1,207 @@
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{
+    check, download_jacket, hashtags, request_error, response_error, MusicProvider, ProviderError,
+};
+use crate::model::{CardData, CardKind};
+
+const API_URL: &str = "https://api.deezer.com";
+/// `deezer.com/track/` and `deezer.com/album/` links, with or without a language, and
+/// `deezer:track:` and `deezer:album:` URIs.
+const CARD_ID: &str = r"^(?:deezer:(track|album):|https?://(?:www\.)?deezer\.com/(?:[a-z]{2}(?:-[a-z]{2})?/)?(track|album)/)(\d+)(?:[?#].*)?$";
+/// Code of the error Deezer answers with, still as 200 OK, when an id does not exist.
+const NO_DATA: u32 = 800;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Reply<T> {
+    Error { error: DeezerError },
+    Ok(T),
+}
+
+#[derive(Deserialize)]
+struct DeezerError {
+    code: u32,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct DeezerTrack {
+    id: u64,
+    title: String,
+    artist: DeezerArtist,
+    /// Main and featured artists, missing from the track lists embedded in other objects.
+    #[serde(default)]
+    contributors: Vec<DeezerArtist>,
+    album: DeezerAlbumRef,
+}
+
+#[derive(Deserialize)]
+struct DeezerArtist {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct DeezerAlbumRef {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct DeezerAlbum {
+    id: u64,
+    title: String,
+    /// `album`, `single`, `ep` or `compile`.
+    record_type: String,
+    /// 1000px jacket.
+    cover_xl: String,
+    genres: DeezerList<DeezerGenre>,
+    artist: DeezerArtist,
+    #[serde(default)]
+    contributors: Vec<DeezerArtist>,
+    release_date: Option<String>,
+    nb_tracks: Option<u32>,
+    /// Total duration in seconds.
+    duration: Option<u64>,
+    label: Option<String>,
+    link: Option<String>,
+}
+
+impl DeezerAlbum {
+    /// Spotify files EPs as singles, and the cards hide the album name of both.
+    fn album_type(&self) -> &'static str {
+        match self.record_type.as_str() {
+            "single" | "ep" => "single",
+            "compile" => "compilation",
+            _ => "album",
+        }
+    }
+
+    fn genres(&self) -> String {
+        hashtags(self.genres.data.iter().map(|genre| genre.name.as_str()))
+    }
+}
+
+/// Main and featured artists joined with ", ", or `artist` alone when they are not listed.
+fn artists(artist: DeezerArtist, contributors: Vec<DeezerArtist>) -> String {
+    if contributors.is_empty() {
+        artist.name
+    } else {
+        contributors.into_iter().map(|artist| artist.name).collect::<Vec<_>>().join(", ")
+    }
+}
+
+#[derive(Deserialize)]
+struct DeezerList<T> {
+    data: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct DeezerGenre {
+    name: String,
+}
+
+/// Tracks and albums from the public Deezer API, which needs no credentials.
+pub struct DeezerProvider {
+    http: Client,
+    api_url: String,
+}
+
+impl Default for DeezerProvider {
+    fn default() -> Self {
+        DeezerProvider { http: Client::new(), api_url: API_URL.to_owned() }
+    }
+}
+
+impl DeezerProvider {
+    pub fn new() -> Self {
+        DeezerProvider::default()
+    }
+
+    /// Sends the API requests somewhere else, e.g. to a stub server in tests.
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    async fn get_json<D: DeserializeOwned>(
+        &self, path: &str, what: &str,
+    ) -> Result<D, ProviderError> {
+        let url = format!("{}{path}", self.api_url);
+        let response = self.http.get(&url).send().await.map_err(request_error)?;
+        match check(response, what)?.json().await.map_err(response_error)? {
+            Reply::Ok(value) => Ok(value),
+            Reply::Error { error } if error.code == NO_DATA => {
+                Err(ProviderError::NotFound(what.to_owned()))
+            }
+            Reply::Error { error } => {
+                Err(ProviderError::Request(format!("{what}: {}", error.message)))
+            }
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl MusicProvider for DeezerProvider {
+    fn name(&self) -> &'static str {
+        "Deezer"
+    }
+
+    fn parse_track_id(&self, input: &str) -> Option<String> {
+        let regex = Regex::new(CARD_ID).unwrap();
+        let captures = regex.captures(input.trim())?;
+        let id = &captures[3];
+        match captures.get(1).or_else(|| captures.get(2)).map(|kind| kind.as_str()) {
+            Some("album") => Some(format!("album:{id}")),
+            _ => Some(id.to_owned()),
+        }
+    }
+
+    async fn track(&self, id: &str) -> Result<CardData, ProviderError> {
+        let track: DeezerTrack =
+            self.get_json(&format!("/track/{id}"), &format!("track {id}")).await?;
+        let album_id = track.album.id;
+        let album: DeezerAlbum =
+            self.get_json(&format!("/album/{album_id}"), &format!("album {album_id}")).await?;
+        let (jacket_size, jacket_bytes) = download_jacket(&self.http, &album.cover_xl).await?;
+
+        Ok(CardData {
+            track_id: track.id.to_string(),
+            name: track.title,
+            album_type: album.album_type().to_owned(),
+            genres: album.genres(),
+            album: album.title,
+            artists: artists(track.artist, track.contributors),
+            jacket_size,
+            jacket_bytes,
+            ..Default::default()
+        })
+    }
+
+    async fn album(&self, id: &str) -> Result<CardData, ProviderError> {
+        let album: DeezerAlbum =
+            self.get_json(&format!("/album/{id}"), &format!("album {id}")).await?;
+        let (jacket_size, jacket_bytes) = download_jacket(&self.http, &album.cover_xl).await?;
+
+        Ok(CardData {
+            track_id: format!("album:{}", album.id),
+            name: album.title.clone(),
+            album_type: album.album_type().to_owned(),
+            genres: album.genres(),
+            album: album.title,
+            artists: artists(album.artist, album.contributors),
+            jacket_size,
+            jacket_bytes,
+            kind: CardKind::Album,
+            release_date: album.release_date,
+            total_tracks: album.nb_tracks,
+            duration_ms: album.duration.map(|seconds| seconds * 1000),
+            label: album.label,
+            external_url: album.link,
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use super::{MusicProvider, ProviderError};
+use crate::model::CardData;
+
+/// Provider answering from tracks kept in memory, to render cards and exercise the UI offline.
+#[derive(Debug, Clone, Default)]
+pub struct FakeProvider {
+    tracks: HashMap<String, CardData>,
+}
+
+impl FakeProvider {
+    pub fn new() -> Self {
+        FakeProvider::default()
+    }
+
+    /// Adds a card of any kind, resolved by its `track_id`.
+    pub fn with_track(mut self, card_data: CardData) -> Self {
+        self.tracks.insert(card_data.track_id.clone(), card_data);
+        self
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl MusicProvider for FakeProvider {
+    fn name(&self) -> &'static str {
+        "Fake"
+    }
+
+    fn parse_track_id(&self, input: &str) -> Option<String> {
+        self.tracks.contains_key(input).then(|| input.to_owned())
+    }
+
+    async fn track(&self, id: &str) -> Result<CardData, ProviderError> {
+        self.tracks.get(id).cloned().ok_or_else(|| ProviderError::NotFound(format!("track {id}")))
+    }
+
+    /// Cards of every kind are kept under their full id, there is nothing to dispatch.
+    async fn card(&self, id: &str) -> Result<CardData, ProviderError> {
+        self.tracks.get(id).cloned().ok_or_else(|| ProviderError::NotFound(id.to_owned()))
+    }
+}
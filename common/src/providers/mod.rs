@@ -0,0 +1,159 @@
+//! Music services the card metadata and jackets are fetched from.
+
+use std::fmt;
+#[cfg(feature = "http")]
+use std::io::Cursor;
+
+use async_trait::async_trait;
+
+use crate::model::CardData;
+
+#[cfg(feature = "http")]
+pub mod apple_music;
+#[cfg(feature = "http")]
+pub mod deezer;
+mod fake;
+#[cfg(feature = "http")]
+pub mod musicbrainz;
+#[cfg(feature = "http")]
+pub mod spotify;
+
+pub use fake::FakeProvider;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    /// The id or link does not point to a track of the provider.
+    InvalidId(String),
+    /// The provider does not know the requested item.
+    NotFound(String),
+    /// The provider could not be reached or answered with an error.
+    Request(String),
+    /// The provider answered with something other than what it documents.
+    Response(String),
+    /// The provider has no cards of the requested kind, e.g. albums.
+    Unsupported(String),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::InvalidId(id) => write!(f, "invalid track id {id:?}"),
+            ProviderError::NotFound(what) => write!(f, "{what} not found"),
+            ProviderError::Request(reason) => write!(f, "request failed: {reason}"),
+            ProviderError::Response(reason) => write!(f, "unexpected response: {reason}"),
+            ProviderError::Unsupported(what) => write!(f, "{what} are not supported"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Source of the metadata and jacket drawn on a card.
+///
+/// Futures are `Send` everywhere but on WASM, where browser requests can not leave their thread.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait MusicProvider {
+    /// Name of the service shown to users, e.g. "Spotify".
+    fn name(&self) -> &'static str;
+
+    /// Extracts the id of the card to fetch with [`MusicProvider::card`] from a link, URI or bare
+    /// id, `None` when `input` does not belong to this provider.
+    fn parse_track_id(&self, input: &str) -> Option<String>;
+
+    /// Resolves a track id into everything drawn on its card, jacket bytes included.
+    async fn track(&self, id: &str) -> Result<CardData, ProviderError>;
+
+    /// Resolves an album id into the card of the whole album.
+    async fn album(&self, _id: &str) -> Result<CardData, ProviderError> {
+        Err(ProviderError::Unsupported(format!("{} albums", self.name())))
+    }
+
+    /// Resolves a playlist id into the card of the playlist and its first tracks.
+    async fn playlist(&self, _id: &str) -> Result<CardData, ProviderError> {
+        Err(ProviderError::Unsupported(format!("{} playlists", self.name())))
+    }
+
+    /// Resolves an artist id into the card of the artist and their top tracks.
+    async fn artist(&self, _id: &str) -> Result<CardData, ProviderError> {
+        Err(ProviderError::Unsupported(format!("{} artists", self.name())))
+    }
+
+    /// Resolves a podcast episode id into its card, along with the show it belongs to.
+    async fn episode(&self, _id: &str) -> Result<CardData, ProviderError> {
+        Err(ProviderError::Unsupported(format!("{} podcasts", self.name())))
+    }
+
+    /// Resolves a podcast id into the card of the whole show.
+    async fn show(&self, _id: &str) -> Result<CardData, ProviderError> {
+        Err(ProviderError::Unsupported(format!("{} podcasts", self.name())))
+    }
+
+    /// Resolves a card id, whose [`CardKind`](crate::model::CardKind) prefix picks what is
+    /// fetched, e.g. `album:` ids go to [`MusicProvider::album`] and bare ids to
+    /// [`MusicProvider::track`].
+    async fn card(&self, id: &str) -> Result<CardData, ProviderError> {
+        match id.split_once(':') {
+            Some(("album", album_id)) => self.album(album_id).await,
+            Some(("playlist", playlist_id)) => self.playlist(playlist_id).await,
+            Some(("artist", artist_id)) => self.artist(artist_id).await,
+            Some(("episode", episode_id)) => self.episode(episode_id).await,
+            Some(("show", show_id)) => self.show(show_id).await,
+            _ => self.track(id).await,
+        }
+    }
+}
+
+/// Turns error statuses into a [`ProviderError`] about `what` was requested.
+#[cfg(feature = "http")]
+pub(crate) fn check(
+    response: reqwest::Response, what: &str,
+) -> Result<reqwest::Response, ProviderError> {
+    match response.status() {
+        status if status.is_success() => Ok(response),
+        reqwest::StatusCode::NOT_FOUND => Err(ProviderError::NotFound(what.to_owned())),
+        reqwest::StatusCode::BAD_REQUEST => Err(ProviderError::InvalidId(what.to_owned())),
+        status => Err(ProviderError::Request(format!("{what}: {status}"))),
+    }
+}
+
+#[cfg(feature = "http")]
+pub(crate) fn request_error(err: reqwest::Error) -> ProviderError {
+    ProviderError::Request(err.to_string())
+}
+
+#[cfg(feature = "http")]
+pub(crate) fn response_error(err: reqwest::Error) -> ProviderError {
+    ProviderError::Response(err.to_string())
+}
+
+/// Width of the jacket of playlist cards, whose covers are drawn as a mosaic of this size.
+pub const JACKET_SIZE: u16 = 640;
+
+/// Downloads a jacket, returning its width along with its bytes since not every provider reports
+/// it.
+#[cfg(feature = "http")]
+pub(crate) async fn download_jacket(
+    http: &reqwest::Client, url: &str,
+) -> Result<(u16, Vec<u8>), ProviderError> {
+    let response = http.get(url).send().await.map_err(request_error)?;
+    let bytes = check(response, "jacket")?.bytes().await.map_err(response_error)?.to_vec();
+    let (width, _) = image::io::Reader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .ok_or_else(|| ProviderError::Response(format!("jacket {url} is not a supported image")))?;
+    let width = u16::try_from(width)
+        .map_err(|_| ProviderError::Response(format!("jacket {url} is {width}px wide")))?;
+    Ok((width, bytes))
+}
+
+/// Turns genre names into the `#genre` list printed on cards.
+#[cfg(feature = "http")]
+pub(crate) fn hashtags<'a>(genres: impl IntoIterator<Item = &'a str>) -> String {
+    genres
+        .into_iter()
+        .map(|genre| format!("#{}", genre.to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,302 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::{header::USER_AGENT, Client};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{
+    check, download_jacket, hashtags, request_error, response_error, MusicProvider, ProviderError,
+};
+use crate::model::CardData;
+
+const API_URL: &str = "https://musicbrainz.org/ws/2";
+const COVER_ART_URL: &str = "https://coverartarchive.org";
+/// MusicBrainz throttles clients that do not say who they are.
+const CLIENT: &str = concat!(
+    "custom-spotify-cards/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/dgarciah98/custom-spotify-cards )"
+);
+const MBID: &str = r"[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}";
+const ISRC: &str = r"^(?:isrc:)?([A-Z]{2}[A-Z0-9]{3}\d{7})$";
+/// Most genres kept from the ones MusicBrainz users voted for.
+const MAX_GENRES: usize = 4;
+
+#[derive(Deserialize)]
+struct Recording {
+    id: String,
+    title: String,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<ArtistCredit>,
+    #[serde(default)]
+    releases: Vec<Release>,
+    #[serde(default)]
+    genres: Vec<Genre>,
+}
+
+#[derive(Deserialize)]
+struct ArtistCredit {
+    name: String,
+    /// Text between this artist and the next one, e.g. " feat. ".
+    #[serde(default)]
+    joinphrase: String,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    id: String,
+    title: String,
+    status: Option<String>,
+    date: Option<String>,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<ArtistCredit>,
+    #[serde(rename = "release-group")]
+    release_group: Option<ReleaseGroup>,
+    #[serde(default)]
+    media: Vec<Medium>,
+    #[serde(default)]
+    genres: Vec<Genre>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseGroup {
+    id: String,
+    #[serde(rename = "primary-type")]
+    primary_type: Option<String>,
+    #[serde(rename = "secondary-types", default)]
+    secondary_types: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Medium {
+    #[serde(default)]
+    tracks: Vec<Track>,
+}
+
+#[derive(Deserialize)]
+struct Track {
+    title: String,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<ArtistCredit>,
+    recording: Option<TrackRecording>,
+}
+
+#[derive(Deserialize)]
+struct TrackRecording {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Genre {
+    name: String,
+    count: u32,
+}
+
+#[derive(Deserialize)]
+struct IsrcLookup {
+    recordings: Vec<TrackRecording>,
+}
+
+impl Release {
+    fn album_type(&self) -> &'static str {
+        let Some(group) = &self.release_group else {
+            return "album";
+        };
+        if group.secondary_types.iter().any(|kind| kind == "Compilation") {
+            "compilation"
+        } else if matches!(group.primary_type.as_deref(), Some("Single" | "EP")) {
+            "single"
+        } else {
+            "album"
+        }
+    }
+}
+
+fn artists(credits: &[ArtistCredit]) -> String {
+    credits.iter().map(|credit| format!("{}{}", credit.name, credit.joinphrase)).collect()
+}
+
+fn genres(mut genres: Vec<Genre>) -> String {
+    genres.sort_by_key(|genre| Reverse(genre.count));
+    hashtags(genres.iter().take(MAX_GENRES).map(|genre| genre.name.as_str()))
+}
+
+/// Open metadata from MusicBrainz, with jackets from the Cover Art Archive. Neither needs keys.
+///
+/// Ids are recording MBIDs, `release:` followed by a release MBID, which resolves to the first
+/// track of the release, or `isrc:` followed by an ISRC.
+pub struct MusicBrainzProvider {
+    http: Client,
+    api_url: String,
+    cover_art_url: String,
+}
+
+impl Default for MusicBrainzProvider {
+    fn default() -> Self {
+        MusicBrainzProvider {
+            http: Client::new(),
+            api_url: API_URL.to_owned(),
+            cover_art_url: COVER_ART_URL.to_owned(),
+        }
+    }
+}
+
+impl MusicBrainzProvider {
+    pub fn new() -> Self {
+        MusicBrainzProvider::default()
+    }
+
+    /// Sends the MusicBrainz requests somewhere else, e.g. to a stub server in tests.
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Downloads the jackets from somewhere else, e.g. from a stub server in tests.
+    pub fn with_cover_art_url(mut self, cover_art_url: &str) -> Self {
+        self.cover_art_url = cover_art_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    async fn get_json<D: DeserializeOwned>(
+        &self, path: &str, what: &str,
+    ) -> Result<D, ProviderError> {
+        let url = format!("{}{path}", self.api_url);
+        let response = self
+            .http
+            .get(&url)
+            .header(USER_AGENT, CLIENT)
+            .query(&[("fmt", "json")])
+            .send()
+            .await
+            .map_err(request_error)?;
+        check(response, what)?.json().await.map_err(response_error)
+    }
+
+    /// Front cover of the release, or else the one chosen for its release group, along with its
+    /// width.
+    async fn jacket(&self, release: &Release) -> Result<(u16, Vec<u8>), ProviderError> {
+        let url = format!("{}/release/{}/front-500", self.cover_art_url, release.id);
+        let result = download_jacket(&self.http, &url).await;
+        match (&result, &release.release_group) {
+            (Err(ProviderError::NotFound(_)), Some(group)) => {
+                let url = format!("{}/release-group/{}/front-500", self.cover_art_url, group.id);
+                download_jacket(&self.http, &url).await
+            }
+            _ => result,
+        }
+    }
+
+    async fn recording(&self, mbid: &str) -> Result<CardData, ProviderError> {
+        let recording: Recording = self
+            .get_json(
+                &format!("/recording/{mbid}?inc=artist-credits+releases+release-groups+genres"),
+                &format!("recording {mbid}"),
+            )
+            .await?;
+        // Official albums first, the oldest one when the recording was released many times.
+        let Some(release) = recording.releases.iter().min_by_key(|release| {
+            (
+                release.status.as_deref() != Some("Official"),
+                release.album_type() != "album",
+                release
+                    .date
+                    .clone()
+                    .filter(|date| !date.is_empty())
+                    .unwrap_or_else(|| "9999".to_owned()),
+            )
+        }) else {
+            return Err(ProviderError::NotFound(format!("release of recording {mbid}")));
+        };
+
+        let (jacket_size, jacket_bytes) = self.jacket(release).await?;
+
+        Ok(CardData {
+            track_id: recording.id.clone(),
+            name: recording.title.clone(),
+            album: release.title.clone(),
+            album_type: release.album_type().to_owned(),
+            artists: artists(&recording.artist_credit),
+            genres: genres(recording.genres),
+            jacket_size,
+            jacket_bytes,
+            ..Default::default()
+        })
+    }
+
+    async fn release(&self, mbid: &str) -> Result<CardData, ProviderError> {
+        let release: Release = self
+            .get_json(
+                &format!("/release/{mbid}?inc=artist-credits+recordings+release-groups+genres"),
+                &format!("release {mbid}"),
+            )
+            .await?;
+        let Some(track) = release.media.iter().flat_map(|medium| &medium.tracks).next() else {
+            return Err(ProviderError::NotFound(format!("tracks of release {mbid}")));
+        };
+        let credits = if track.artist_credit.is_empty() {
+            &release.artist_credit
+        } else {
+            &track.artist_credit
+        };
+        let (jacket_size, jacket_bytes) = self.jacket(&release).await?;
+
+        Ok(CardData {
+            track_id: track
+                .recording
+                .as_ref()
+                .map_or_else(|| mbid.to_owned(), |recording| recording.id.clone()),
+            name: track.title.clone(),
+            album: release.title.clone(),
+            album_type: release.album_type().to_owned(),
+            artists: artists(credits),
+            genres: genres(release.genres),
+            jacket_size,
+            jacket_bytes,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl MusicProvider for MusicBrainzProvider {
+    fn name(&self) -> &'static str {
+        "MusicBrainz"
+    }
+
+    fn parse_track_id(&self, input: &str) -> Option<String> {
+        let input = input.trim();
+        if let Some(captures) = Regex::new(ISRC).unwrap().captures(input) {
+            return Some(format!("isrc:{}", &captures[1]));
+        }
+
+        let regex = Regex::new(&format!(
+            r"^(?:https?://(?:beta\.)?musicbrainz\.org/(recording|release)/|(release):)?({MBID})(?:[/?#].*)?$"
+        ))
+        .unwrap();
+        let captures = regex.captures(input)?;
+        let kind = captures.get(1).or_else(|| captures.get(2)).map(|kind| kind.as_str());
+        match kind {
+            Some("release") => Some(format!("release:{}", &captures[3])),
+            _ => Some(captures[3].to_owned()),
+        }
+    }
+
+    async fn track(&self, id: &str) -> Result<CardData, ProviderError> {
+        match id.split_once(':') {
+            None => self.recording(id).await,
+            Some(("release", mbid)) => self.release(mbid).await,
+            Some(("isrc", isrc)) => {
+                let lookup: IsrcLookup =
+                    self.get_json(&format!("/isrc/{isrc}"), &format!("ISRC {isrc}")).await?;
+                match lookup.recordings.first() {
+                    Some(recording) => self.recording(&recording.id).await,
+                    None => Err(ProviderError::NotFound(format!("ISRC {isrc}"))),
+                }
+            }
+            Some(_) => Err(ProviderError::InvalidId(id.to_owned())),
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::{header::AUTHORIZATION, Client, StatusCode};
+use serde::de::DeserializeOwned;
+
+use super::{
+    check, download_jacket, request_error, response_error, MusicProvider, ProviderError,
+    JACKET_SIZE,
+};
+use crate::{
+    cards::mosaic_jacket,
+    model::{
+        AccessToken, Album, AlbumTrack, Artist, CardData, CardKind, Episode, Image, ListedTrack,
+        Page, Playlist, Show, TopTracks, Track,
+    },
+};
+
+const API_URL: &str = "https://api.spotify.com/v1";
+/// Bare track ids, and `spotify:` URIs and `open.spotify.com` links of tracks, albums, playlists,
+/// artists, podcast episodes and shows, localized or not.
+const CARD_ID: &str = r"^(?:spotify:(track|album|playlist|artist|episode|show):|https?://open\.spotify\.com/(?:intl-[a-zA-Z-]+/)?(track|album|playlist|artist|episode|show)/)?([a-zA-Z0-9]{22})(?:[?#].*)?$";
+/// Country the Web API answers for when it needs one, e.g. for top tracks and podcasts.
+const MARKET: &str = "US";
+/// Largest page of album tracks the Web API hands out.
+const TRACKS_PAGE_SIZE: u32 = 50;
+/// Tracks of a playlist kept in its card data, more than any card lists.
+const LISTED_TRACKS: usize = 10;
+/// Covers needed for the largest mosaic.
+const MOSAIC_COVERS: usize = 9;
+
+/// Where [`SpotifyProvider`] gets its access tokens from.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait TokenSource {
+    /// Returns a token for the Web API, called again whenever Spotify rejects the previous one.
+    async fn token(&self) -> Result<AccessToken, ProviderError>;
+}
+
+/// Tokens handed out by the `GET /token` endpoint of the card server, which keeps the client
+/// secret to itself.
+pub struct TokenEndpoint {
+    http: Client,
+    url: String,
+}
+
+impl TokenEndpoint {
+    pub fn new(base_url: &str) -> Self {
+        TokenEndpoint {
+            http: Client::new(),
+            url: format!("{}/token", base_url.trim_end_matches('/')),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TokenSource for TokenEndpoint {
+    async fn token(&self) -> Result<AccessToken, ProviderError> {
+        let response = self.http.get(&self.url).send().await.map_err(request_error)?;
+        check(response, "token")?.json().await.map_err(response_error)
+    }
+}
+
+/// Tracks from the Spotify Web API.
+pub struct SpotifyProvider<T> {
+    http: Client,
+    api_url: String,
+    tokens: T,
+    /// Token used until Spotify rejects it, tokens do not come with a clock on every platform.
+    token: Mutex<Option<AccessToken>>,
+}
+
+impl<T: TokenSource> SpotifyProvider<T> {
+    pub fn new(tokens: T) -> Self {
+        SpotifyProvider {
+            http: Client::new(),
+            api_url: API_URL.to_owned(),
+            tokens,
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Sends the Web API requests somewhere else, e.g. to a stub server in tests.
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn tokens(&self) -> &T {
+        &self.tokens
+    }
+
+    async fn bearer(&self, renew: bool) -> Result<String, ProviderError> {
+        let cached = if renew { None } else { self.token.lock().unwrap().clone() };
+        let token = match cached {
+            Some(token) => token,
+            None => {
+                let token = self.tokens.token().await?;
+                *self.token.lock().unwrap() = Some(token.clone());
+                token
+            }
+        };
+        Ok(format!("{} {}", token.token_type, token.access_token))
+    }
+
+    async fn get_json<D: DeserializeOwned>(
+        &self, path: &str, what: &str,
+    ) -> Result<D, ProviderError> {
+        let url = format!("{}{path}", self.api_url);
+        let send = |bearer: String| self.http.get(&url).header(AUTHORIZATION, bearer).send();
+
+        let mut response = send(self.bearer(false).await?).await.map_err(request_error)?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            response = send(self.bearer(true).await?).await.map_err(request_error)?;
+        }
+        check(response, what)?.json().await.map_err(response_error)
+    }
+
+    /// Genres of the first artist, Spotify does not tag tracks and albums themselves.
+    async fn genres(&self, artists: &[Artist]) -> Result<String, ProviderError> {
+        let Some(artist) = artists.first() else {
+            return Ok(String::new());
+        };
+        let artist: Artist = self
+            .get_json(&format!("/artists/{}", artist.id), &format!("artist {}", artist.id))
+            .await?;
+        Ok(artist.genres().unwrap_or_default())
+    }
+
+    /// Downloads the largest of the `images` of `what`, returning its width along with the bytes.
+    async fn jacket(&self, images: &[Image], what: &str) -> Result<(u16, Vec<u8>), ProviderError> {
+        // Spotify lists the image sizes from the largest to the smallest.
+        let Some(image) = images.first() else {
+            return Err(ProviderError::NotFound(format!("jacket of {what}")));
+        };
+        let jacket = self.http.get(&image.url).send().await.map_err(request_error)?;
+        let jacket_bytes = check(jacket, "jacket")?.bytes().await.map_err(response_error)?;
+        Ok((image.width, jacket_bytes.to_vec()))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: TokenSource + Sync> MusicProvider for SpotifyProvider<T> {
+    fn name(&self) -> &'static str {
+        "Spotify"
+    }
+
+    fn parse_track_id(&self, input: &str) -> Option<String> {
+        let regex = Regex::new(CARD_ID).unwrap();
+        let captures = regex.captures(input.trim())?;
+        let id = &captures[3];
+        match captures.get(1).or_else(|| captures.get(2)).map(|kind| kind.as_str()) {
+            Some("track") | None => Some(id.to_owned()),
+            Some(kind) => Some(format!("{kind}:{id}")),
+        }
+    }
+
+    async fn track(&self, id: &str) -> Result<CardData, ProviderError> {
+        let track: Track = self.get_json(&format!("/tracks/{id}"), &format!("track {id}")).await?;
+        if track.artists.is_empty() {
+            return Err(ProviderError::Response(format!("track {id} has no artists")));
+        }
+        let genres = self.genres(&track.artists).await?;
+        let (jacket_size, jacket_bytes) =
+            self.jacket(&track.album.images, &format!("track {id}")).await?;
+
+        Ok(CardData {
+            track_id: track.id.clone(),
+            name: track.name.clone(),
+            album: track.album.name.clone(),
+            album_type: track.album.album_type.clone(),
+            artists: track.artists(),
+            genres,
+            jacket_size,
+            jacket_bytes,
+            release_date: track.album.release_date(),
+            total_tracks: track.album.total_tracks,
+            duration_ms: Some(track.duration_ms),
+            explicit: track.explicit,
+            popularity: track.popularity,
+            disc_number: track.disc_number,
+            track_number: track.track_number,
+            isrc: track.external_ids.isrc,
+            external_url: track.external_urls.spotify,
+            ..Default::default()
+        })
+    }
+
+    async fn album(&self, id: &str) -> Result<CardData, ProviderError> {
+        let mut album: Album =
+            self.get_json(&format!("/albums/{id}"), &format!("album {id}")).await?;
+        let Some(mut tracks) = album.tracks.take() else {
+            return Err(ProviderError::Response(format!("album {id} has no track list")));
+        };
+        // The album only embeds the first page of its tracks, the rest are needed for the duration.
+        while tracks.next.is_some() && tracks.items.len() < tracks.total as usize {
+            let offset = tracks.items.len();
+            let page: Page<AlbumTrack> = self
+                .get_json(
+                    &format!("/albums/{id}/tracks?offset={offset}&limit={TRACKS_PAGE_SIZE}"),
+                    &format!("tracks of album {id}"),
+                )
+                .await?;
+            if page.items.is_empty() {
+                break;
+            }
+            tracks.items.extend(page.items);
+            tracks.next = page.next;
+        }
+        let genres = self.genres(&album.artists).await?;
+        let (jacket_size, jacket_bytes) =
+            self.jacket(&album.images, &format!("album {id}")).await?;
+
+        Ok(CardData {
+            track_id: format!("album:{}", album.id),
+            name: album.name.clone(),
+            album: album.name.clone(),
+            album_type: album.album_type.clone(),
+            artists: album.artists(),
+            genres,
+            jacket_size,
+            jacket_bytes,
+            kind: CardKind::Album,
+            release_date: album.release_date(),
+            total_tracks: Some(album.total_tracks.unwrap_or(tracks.total)),
+            duration_ms: Some(tracks.items.iter().map(|track| track.duration_ms).sum()),
+            label: album.label,
+            popularity: album.popularity,
+            external_url: album.external_urls.spotify,
+            ..Default::default()
+        })
+    }
+
+    async fn playlist(&self, id: &str) -> Result<CardData, ProviderError> {
+        let playlist: Playlist =
+            self.get_json(&format!("/playlists/{id}"), &format!("playlist {id}")).await?;
+        let tracks: Vec<_> =
+            playlist.tracks.items.iter().filter_map(|item| item.track.as_ref()).collect();
+
+        // Albums with several tracks in the playlist only take one tile of the mosaic.
+        let mut album_ids = Vec::new();
+        let mut covers = Vec::new();
+        for album in tracks.iter().filter_map(|track| track.album.as_ref()) {
+            let (Some(album_id), Some(image)) = (&album.id, album.images.first()) else {
+                continue;
+            };
+            if album_ids.contains(album_id) {
+                continue;
+            }
+            album_ids.push(album_id.clone());
+            covers.push(download_jacket(&self.http, &image.url).await?.1);
+            if covers.len() == MOSAIC_COVERS {
+                break;
+            }
+        }
+        let mosaic = mosaic_jacket(&covers, JACKET_SIZE.into())
+            .map_err(|err| ProviderError::Response(format!("covers of playlist {id}: {err}")))?;
+        let (jacket_size, jacket_bytes) = match (mosaic, playlist.images.first()) {
+            (Some(mosaic), _) => (JACKET_SIZE, mosaic),
+            (None, Some(image)) => download_jacket(&self.http, &image.url).await?,
+            (None, None) => {
+                return Err(ProviderError::NotFound(format!("jacket of playlist {id}")))
+            }
+        };
+
+        let tracklist = tracks
+            .iter()
+            .take(LISTED_TRACKS)
+            .map(|track| ListedTrack {
+                name: track.name.clone(),
+                artists: track
+                    .artists
+                    .iter()
+                    .map(|artist| artist.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+            .collect();
+
+        Ok(CardData {
+            track_id: format!("playlist:{}", playlist.id),
+            name: playlist.name,
+            album_type: String::from("playlist"),
+            jacket_size,
+            jacket_bytes,
+            kind: CardKind::Playlist,
+            total_tracks: Some(playlist.tracks.total),
+            owner: Some(playlist.owner.display_name.unwrap_or(playlist.owner.id)),
+            followers: Some(playlist.followers.total),
+            tracklist,
+            ..Default::default()
+        })
+    }
+
+    async fn artist(&self, id: &str) -> Result<CardData, ProviderError> {
+        let artist: Artist =
+            self.get_json(&format!("/artists/{id}"), &format!("artist {id}")).await?;
+        let top_tracks: TopTracks = self
+            .get_json(
+                &format!("/artists/{id}/top-tracks?market={MARKET}"),
+                &format!("top tracks of artist {id}"),
+            )
+            .await?;
+        let (jacket_size, jacket_bytes) =
+            self.jacket(&artist.images, &format!("artist {id}")).await?;
+
+        let tracklist = top_tracks
+            .tracks
+            .iter()
+            .map(|track| ListedTrack {
+                name: track.name.clone(),
+                artists: track
+                    .artists
+                    .iter()
+                    .filter(|featured| featured.id != artist.id)
+                    .map(|featured| featured.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+            .collect();
+
+        Ok(CardData {
+            track_id: format!("artist:{}", artist.id),
+            name: artist.name.clone(),
+            album_type: String::from("artist"),
+            artists: artist.name.clone(),
+            genres: artist.genres().unwrap_or_default(),
+            jacket_size,
+            jacket_bytes,
+            kind: CardKind::Artist,
+            followers: artist.followers.map(|followers| followers.total),
+            tracklist,
+            ..Default::default()
+        })
+    }
+
+    async fn episode(&self, id: &str) -> Result<CardData, ProviderError> {
+        let episode: Episode = self
+            .get_json(&format!("/episodes/{id}?market={MARKET}"), &format!("episode {id}"))
+            .await?;
+        let images = if episode.images.is_empty() { &episode.show.images } else { &episode.images };
+        let (jacket_size, jacket_bytes) = self.jacket(images, &format!("episode {id}")).await?;
+
+        Ok(CardData {
+            track_id: format!("episode:{}", episode.id),
+            name: episode.name,
+            album: episode.show.name,
+            album_type: String::from("episode"),
+            artists: episode.show.publisher,
+            jacket_size,
+            jacket_bytes,
+            kind: CardKind::Episode,
+            release_date: Some(episode.release_date),
+            duration_ms: Some(episode.duration_ms),
+            ..Default::default()
+        })
+    }
+
+    async fn show(&self, id: &str) -> Result<CardData, ProviderError> {
+        let show: Show =
+            self.get_json(&format!("/shows/{id}?market={MARKET}"), &format!("show {id}")).await?;
+        let (jacket_size, jacket_bytes) = self.jacket(&show.images, &format!("show {id}")).await?;
+
+        Ok(CardData {
+            track_id: format!("show:{}", show.id),
+            name: show.name.clone(),
+            album: show.name,
+            album_type: String::from("show"),
+            artists: show.publisher,
+            jacket_size,
+            jacket_bytes,
+            kind: CardKind::Show,
+            total_tracks: show.total_episodes,
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,214 @@
+//! Card data read from the tags and cover art embedded in local audio files.
+
+use std::{fmt, fs::File, io::Cursor, path::Path};
+
+use symphonia::core::{
+    errors::Error as SymphoniaError,
+    formats::FormatOptions,
+    io::{MediaSource, MediaSourceStream},
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey},
+    probe::Hint,
+};
+
+use crate::model::CardData;
+
+/// Keys of the release type tag written by Picard, which Symphonia only recognizes in MP4 files.
+const RELEASE_TYPE_KEYS: &[&str] = &["TXXX:MusicBrainz Album Type", "RELEASETYPE"];
+/// Keys of the compilation flag that Symphonia does not recognize: the iTunes ID3v2 frames, and the
+/// Vorbis comment it only knows misspelled.
+const COMPILATION_KEYS: &[&str] = &["TCMP", "TCP", "COMPILATION"];
+
+#[derive(Debug)]
+pub enum TagError {
+    /// The file is not a supported audio format, or its tags could not be parsed.
+    Read(SymphoniaError),
+    /// The file has no title tag.
+    NoTitle,
+    /// The file has no embedded picture to use as jacket.
+    NoCover,
+    /// The embedded picture is not an image format the cards can be drawn with.
+    CoverDecode(image::ImageError),
+    /// The embedded picture is wider than a jacket can be, in pixels.
+    CoverTooLarge(u32),
+}
+
+impl fmt::Display for TagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagError::Read(err) => write!(f, "could not read audio tags: {err}"),
+            TagError::NoTitle => write!(f, "the audio file has no title"),
+            TagError::NoCover => write!(f, "the audio file has no embedded cover art"),
+            TagError::CoverDecode(err) => write!(f, "could not decode the cover art: {err}"),
+            TagError::CoverTooLarge(width) => {
+                write!(f, "the cover art is too large ({width}px wide)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TagError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TagError::Read(err) => Some(err),
+            TagError::CoverDecode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SymphoniaError> for TagError {
+    fn from(err: SymphoniaError) -> Self {
+        TagError::Read(err)
+    }
+}
+
+/// Card fields found in an MP3, FLAC, M4A or Ogg file. Anything the file does not say is left
+/// empty, so callers can fill it in before building the [`CardData`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub album: Option<String>,
+    /// `album`, `single` or `compilation`, from the MusicBrainz release type or compilation flag.
+    pub album_type: Option<String>,
+    /// Every artist, joined with ", ".
+    pub artists: Option<String>,
+    /// Genres as they are printed, e.g. "#pop #rock".
+    pub genres: Option<String>,
+    /// Front cover, or the first picture when none is marked as such.
+    pub cover: Option<Vec<u8>>,
+}
+
+impl AudioTags {
+    /// Reads the tags of an audio file, guessing its format from the contents and extension.
+    pub fn from_path(path: &Path) -> Result<Self, TagError> {
+        let file = File::open(path).map_err(SymphoniaError::IoError)?;
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        AudioTags::read(Box::new(file), extension)
+    }
+
+    /// Reads the tags of an audio file held in memory, like an upload.
+    pub fn from_bytes(bytes: Vec<u8>, extension: Option<&str>) -> Result<Self, TagError> {
+        AudioTags::read(Box::new(Cursor::new(bytes)), extension)
+    }
+
+    fn read(source: Box<dyn MediaSource>, extension: Option<&str>) -> Result<Self, TagError> {
+        let mut hint = Hint::new();
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
+        }
+        let stream = MediaSourceStream::new(source, Default::default());
+        let mut probed = symphonia::default::get_probe().format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+
+        // ID3v2 tags come before the MP3 stream and are found while probing, the tags of the
+        // other formats are part of the container.
+        let mut tags = AudioTags::default();
+        if let Some(revision) = probed.metadata.get().as_ref().and_then(|log| log.current()) {
+            tags.merge(revision);
+        }
+        if let Some(revision) = probed.format.metadata().current() {
+            tags.merge(revision);
+        }
+        Ok(tags)
+    }
+
+    /// Fills the fields that are still empty from a metadata revision.
+    fn merge(&mut self, revision: &MetadataRevision) {
+        let first = |key: StandardTagKey| values(revision, key, &[]).next();
+        // Tags listing several values may be repeated, ID3v2.4 separates them with NUL and taggers
+        // often use semicolons.
+        let list = |key: StandardTagKey| -> Option<Vec<String>> {
+            let list: Vec<_> = values(revision, key, &[])
+                .flat_map(|value| {
+                    value.split(['\0', ';']).map(|item| item.trim().to_owned()).collect::<Vec<_>>()
+                })
+                .filter(|item| !item.is_empty())
+                .collect();
+            (!list.is_empty()).then_some(list)
+        };
+
+        self.title = self.title.take().or_else(|| first(StandardTagKey::TrackTitle));
+        self.album = self.album.take().or_else(|| first(StandardTagKey::Album));
+        self.artists = self.artists.take().or_else(|| {
+            list(StandardTagKey::Artist)
+                .or_else(|| list(StandardTagKey::AlbumArtist))
+                .map(|artists| artists.join(", "))
+        });
+        self.genres = self.genres.take().or_else(|| {
+            let genres = list(StandardTagKey::Genre)?;
+            let hashtags: Vec<_> =
+                genres.iter().map(|genre| format!("#{}", genre.to_lowercase())).collect();
+            Some(hashtags.join(" "))
+        });
+        self.album_type = self.album_type.take().or_else(|| {
+            let compilation = values(revision, StandardTagKey::Compilation, COMPILATION_KEYS)
+                .any(|value| value == "1" || value == "true");
+            // Picard writes the primary and secondary types together, e.g. "album/compilation".
+            let release_type =
+                values(revision, StandardTagKey::MusicBrainzReleaseType, RELEASE_TYPE_KEYS)
+                    .next()
+                    .unwrap_or_default()
+                    .to_lowercase();
+            let types: Vec<_> = release_type.split(|c: char| !c.is_alphanumeric()).collect();
+            if compilation || types.contains(&"compilation") {
+                Some("compilation".to_owned())
+            } else if types.contains(&"single") || types.contains(&"ep") {
+                Some("single".to_owned())
+            } else if types.contains(&"album") {
+                Some("album".to_owned())
+            } else {
+                None
+            }
+        });
+        self.cover = self.cover.take().or_else(|| {
+            let visuals = revision.visuals();
+            visuals
+                .iter()
+                .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+                .or_else(|| visuals.first())
+                .map(|visual| visual.data.to_vec())
+        });
+    }
+
+    /// Builds the card data, which needs at least a title and a cover. The jacket is as wide as
+    /// the cover.
+    pub fn card_data(self) -> Result<CardData, TagError> {
+        let cover = self.cover.ok_or(TagError::NoCover)?;
+        let (width, _) = image::io::Reader::new(Cursor::new(&cover))
+            .with_guessed_format()
+            .map_err(|err| TagError::CoverDecode(err.into()))?
+            .into_dimensions()
+            .map_err(TagError::CoverDecode)?;
+        let jacket_size = u16::try_from(width).map_err(|_| TagError::CoverTooLarge(width))?;
+
+        Ok(CardData {
+            track_id: String::new(),
+            name: self.title.ok_or(TagError::NoTitle)?,
+            album: self.album.unwrap_or_default(),
+            album_type: self.album_type.unwrap_or_else(|| "album".to_owned()),
+            artists: self.artists.unwrap_or_default(),
+            genres: self.genres.unwrap_or_default(),
+            jacket_size,
+            jacket_bytes: cover,
+            ..Default::default()
+        })
+    }
+}
+
+/// Non-empty values of the tags with the given standard key, or with one of the given raw keys.
+fn values<'a>(
+    revision: &'a MetadataRevision, key: StandardTagKey, raw_keys: &'a [&str],
+) -> impl Iterator<Item = String> + 'a {
+    revision
+        .tags()
+        .iter()
+        .filter(move |tag| {
+            tag.std_key == Some(key) || raw_keys.iter().any(|raw| tag.key.eq_ignore_ascii_case(raw))
+        })
+        .map(|tag| tag.value.to_string().trim().to_owned())
+        .filter(|value| !value.is_empty())
+}
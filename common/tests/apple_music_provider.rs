@@ -0,0 +1,138 @@
+#![cfg(feature = "http")]
+
+mod support;
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Router,
+};
+use common::{
+    model::CardKind,
+    providers::{apple_music::AppleMusicProvider, MusicProvider, ProviderError},
+};
+use support::{fixture, jacket_png, serve_with_url, BaseUrl};
+
+const TRACK_ID: &str = "697195462";
+const SINGLE_ID: &str = "1440839661";
+const ALBUM_ID: &str = "697194953";
+
+async fn stub_lookup(
+    State(base_url): State<BaseUrl>, Query(query): Query<HashMap<String, String>>,
+) -> String {
+    let base_url = base_url.get().unwrap();
+    match (query.get("id").map(String::as_str), query.get("entity").map(String::as_str)) {
+        (Some(ALBUM_ID), Some("song")) => fixture("apple_music/album.json", base_url),
+        (Some(TRACK_ID), None) => fixture("apple_music/lookup.json", base_url),
+        (Some(SINGLE_ID), None) => fixture("apple_music/single.json", base_url),
+        _ => fixture("apple_music/empty.json", base_url),
+    }
+}
+
+/// Only serves the upscaled artwork, the lookup lists 100px jackets.
+async fn stub_artwork(Path(path): Path<String>) -> Result<Vec<u8>, StatusCode> {
+    if path.ends_with("/1000x1000bb.jpg") {
+        Ok(jacket_png())
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+async fn provider() -> AppleMusicProvider {
+    let base_url = serve_with_url(|base_url| {
+        Router::new()
+            .route("/lookup", get(stub_lookup))
+            .route("/image/thumb/*path", get(stub_artwork))
+            .with_state(base_url)
+    })
+    .await;
+    AppleMusicProvider::new().with_api_url(&base_url)
+}
+
+#[tokio::test]
+async fn tracks_are_mapped_into_card_data() {
+    let card_data = provider().await.track(TRACK_ID).await.unwrap();
+
+    assert_eq!(card_data.track_id, TRACK_ID);
+    assert_eq!(card_data.name, "Harder, Better, Faster, Stronger");
+    assert_eq!(card_data.album, "Discovery");
+    assert_eq!(card_data.album_type, "album");
+    assert_eq!(card_data.artists, "Daft Punk");
+    assert_eq!(card_data.genres, "#electronic");
+    // Width of the served jacket, which the provider does not report.
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+}
+
+#[tokio::test]
+async fn singles_lose_their_suffix() {
+    let card_data = provider().await.track(SINGLE_ID).await.unwrap();
+
+    assert_eq!(card_data.album, "Get Lucky (feat. Pharrell Williams & Nile Rodgers) [Radio Edit]");
+    assert_eq!(card_data.album_type, "single");
+}
+
+#[tokio::test]
+async fn albums_are_mapped_into_card_data() {
+    let card_data = provider().await.card(&format!("album:{ALBUM_ID}")).await.unwrap();
+
+    assert_eq!(card_data.track_id, "album:697194953");
+    assert_eq!(card_data.kind, CardKind::Album);
+    assert_eq!(card_data.name, "Discovery");
+    assert_eq!(card_data.album, "Discovery");
+    assert_eq!(card_data.album_type, "album");
+    assert_eq!(card_data.artists, "Daft Punk");
+    assert_eq!(card_data.genres, "#electronic");
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+    assert_eq!(card_data.release_date.as_deref(), Some("2001-03-07"));
+    assert_eq!(card_data.total_tracks, Some(14));
+    // Only the listed songs count towards the duration.
+    assert_eq!(card_data.duration_ms, Some(545_050));
+    assert_eq!(
+        card_data.external_url.as_deref(),
+        Some("https://music.apple.com/us/album/discovery/697194953?uo=4")
+    );
+}
+
+#[tokio::test]
+async fn unknown_ids_are_not_found() {
+    let result = provider().await.track("1").await;
+    assert!(matches!(result, Err(ProviderError::NotFound(_))), "{result:?}");
+
+    let result = provider().await.album("1").await;
+    assert!(matches!(result, Err(ProviderError::NotFound(_))), "{result:?}");
+}
+
+#[test]
+fn track_ids_are_parsed_from_links() {
+    let provider = AppleMusicProvider::new();
+
+    for input in [
+        "https://music.apple.com/us/album/harder-better-faster-stronger/697194953?i=697195462",
+        "https://music.apple.com/us/song/harder-better-faster-stronger/697195462",
+        "https://music.apple.com/us/song/697195462",
+        "https://itunes.apple.com/us/album/discovery/id697194953?i=697195462&uo=4",
+        "https://geo.music.apple.com/us/album/_/697194953?i=697195462&mt=1&app=music",
+    ] {
+        assert_eq!(provider.parse_track_id(input).as_deref(), Some(TRACK_ID), "{input}");
+    }
+    for input in [
+        "https://music.apple.com/us/album/discovery/697194953",
+        "https://music.apple.com/us/album/697194953?l=fr",
+        "https://itunes.apple.com/us/album/discovery/id697194953",
+    ] {
+        let id = provider.parse_track_id(input);
+        assert_eq!(id.as_deref(), Some("album:697194953"), "{input}");
+    }
+    for input in [
+        "https://music.apple.com/us/artist/daft-punk/5468295",
+        "https://www.deezer.com/track/3135556",
+        "697195462",
+    ] {
+        assert_eq!(provider.parse_track_id(input), None, "{input}");
+    }
+}
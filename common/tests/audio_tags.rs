@@ -0,0 +1,73 @@
+#![cfg(feature = "tags")]
+
+use std::{fs, io::Cursor, path::PathBuf};
+
+use common::tags::{AudioTags, TagError};
+use image::{ImageOutputFormat, RgbaImage};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(format!("{}/tests/fixtures/audio/{name}", env!("CARGO_MANIFEST_DIR")))
+}
+
+fn cover() -> Vec<u8> {
+    fs::read(fixture("cover.png")).unwrap()
+}
+
+#[test]
+fn tags_are_read_from_every_format() {
+    for (file, artists, album_type) in [
+        ("tagged.mp3", "Daft Punk, Romanthony", "album"),
+        ("tagged.flac", "Daft Punk", "compilation"),
+        ("tagged.m4a", "Daft Punk", "single"),
+        ("tagged.ogg", "Daft Punk", "single"),
+    ] {
+        let card_data = AudioTags::from_path(&fixture(file)).unwrap().card_data().unwrap();
+
+        assert_eq!(card_data.name, "Harder, Better, Faster, Stronger", "{file}");
+        assert_eq!(card_data.album, "Discovery", "{file}");
+        assert_eq!(card_data.album_type, album_type, "{file}");
+        assert_eq!(card_data.artists, artists, "{file}");
+        assert_eq!(card_data.genres, "#electronic #house", "{file}");
+        // As wide as the cover.
+        assert_eq!(card_data.jacket_size, 16, "{file}");
+        assert_eq!(card_data.jacket_bytes, cover(), "{file}");
+    }
+}
+
+#[test]
+fn uploads_are_read_from_memory() {
+    let bytes = fs::read(fixture("tagged.flac")).unwrap();
+    let tags = AudioTags::from_bytes(bytes, None).unwrap();
+    assert_eq!(tags.title.as_deref(), Some("Harder, Better, Faster, Stronger"));
+    assert_eq!(tags.cover, Some(cover()));
+}
+
+#[test]
+fn missing_fields_can_be_filled_in() {
+    let tags = AudioTags::from_path(&fixture("no_cover.mp3")).unwrap();
+    assert!(matches!(tags.clone().card_data(), Err(TagError::NoCover)));
+
+    let card_data = AudioTags { cover: Some(cover()), ..tags }.card_data().unwrap();
+    assert_eq!(card_data.jacket_bytes, cover());
+}
+
+#[test]
+fn other_files_are_rejected() {
+    let result = AudioTags::from_path(&fixture("cover.png"));
+    assert!(matches!(result, Err(TagError::Read(_))), "{result:?}");
+}
+
+#[test]
+fn covers_must_be_images_a_jacket_can_be() {
+    let tags = AudioTags { title: Some(String::from("Song")), ..Default::default() };
+
+    let not_an_image = AudioTags { cover: Some(b"not an image".to_vec()), ..tags.clone() };
+    assert!(matches!(not_an_image.card_data(), Err(TagError::CoverDecode(_))));
+
+    let mut too_wide = Vec::new();
+    RgbaImage::new(70_000, 1)
+        .write_to(&mut Cursor::new(&mut too_wide), ImageOutputFormat::Png)
+        .unwrap();
+    let too_wide = AudioTags { cover: Some(too_wide), ..tags };
+    assert!(matches!(too_wide.card_data(), Err(TagError::CoverTooLarge(70_000))));
+}
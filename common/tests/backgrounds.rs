@@ -0,0 +1,136 @@
+mod support;
+
+use common::{
+    cards::generate_card_from_spec,
+    model::{Background, CardColors, CardData, CardFields, CardSpec, ColorStop, GradientShape},
+};
+use image::{DynamicImage, GenericImageView, Rgba};
+use support::jacket_png;
+
+/// Renders a card without any text, so that only the background and jacket are drawn.
+fn render(spec: CardSpec) -> DynamicImage {
+    let card_data = CardData {
+        track_id: String::from("test"),
+        album_type: String::from("album"),
+        jacket_size: 64,
+        jacket_bytes: jacket_png(),
+        ..Default::default()
+    };
+    let spec = CardSpec {
+        fields: CardFields {
+            name: false,
+            album: false,
+            artists: false,
+            genres: false,
+            ..Default::default()
+        },
+        ..spec
+    };
+
+    image::load_from_memory(&generate_card_from_spec(card_data, &spec).unwrap()).unwrap()
+}
+
+#[test]
+fn blurred_jackets_differ_from_plain_backgrounds() {
+    let plain = render(CardSpec { background: Background::Plain, ..Default::default() });
+    let blurred = render(CardSpec { background: Background::BlurredJacket, ..Default::default() });
+    assert_eq!(plain.dimensions(), blurred.dimensions());
+
+    // The right of the card is only background, a single color for plain cards.
+    let x = plain.width() - 1;
+    assert_eq!(plain.get_pixel(x, 0), plain.get_pixel(x, plain.height() - 1));
+    assert_ne!(blurred.get_pixel(x, 0), blurred.get_pixel(x, blurred.height() - 1));
+    assert_ne!(plain.get_pixel(x, 0), blurred.get_pixel(x, 0));
+}
+
+/// Renders a custom gradient through `stops` given as `(color, position)`, on a 178x108 card whose
+/// jacket covers `30..78` on both axes.
+fn render_gradient(gradient: GradientShape, stops: &[(&str, f32)]) -> DynamicImage {
+    let stops = stops
+        .iter()
+        .map(|(color, position)| ColorStop { color: color.to_string(), position: *position })
+        .collect();
+    render(CardSpec {
+        background: Background::Custom,
+        gradient,
+        colors: CardColors { custom_stops: Some(stops) },
+        ..Default::default()
+    })
+}
+
+#[track_caller]
+fn assert_color(card: &DynamicImage, (x, y): (u32, u32), expected: [u8; 3]) {
+    let Rgba([r, g, b, _]) = card.get_pixel(x, y);
+    let close = [r, g, b].iter().zip(expected).all(|(a, b)| a.abs_diff(b) <= 4);
+    assert!(close, "pixel at {x},{y} is {:?}, expected {expected:?}", [r, g, b]);
+}
+
+const RED_TO_BLUE: [(&str, f32); 2] = [("#ff0000", 0.0), ("#0000ff", 1.0)];
+
+#[test]
+fn linear_gradients_follow_their_angle() {
+    let card = render_gradient(GradientShape::Linear { angle: 180.0 }, &RED_TO_BLUE);
+    assert_eq!(card.dimensions(), (178, 108));
+    assert_color(&card, (177, 0), [254, 0, 1]);
+    assert_color(&card, (177, 53), [128, 0, 127]);
+    assert_color(&card, (177, 107), [1, 0, 254]);
+
+    let card = render_gradient(GradientShape::Linear { angle: 90.0 }, &RED_TO_BLUE);
+    assert_color(&card, (0, 0), [254, 0, 1]);
+    assert_color(&card, (88, 0), [128, 0, 127]);
+    assert_color(&card, (177, 107), [1, 0, 254]);
+}
+
+#[test]
+fn gradient_stops_keep_their_positions() {
+    let stops = [("#ff0000", 0.0), ("#00ff00", 0.25), ("#0000ff", 1.0)];
+    let card = render_gradient(GradientShape::Linear { angle: 180.0 }, &stops);
+    // Rows 26 and 27 straddle the quarter of the height.
+    assert_color(&card, (177, 26), [5, 250, 0]);
+    assert_color(&card, (177, 27), [0, 252, 3]);
+    assert_color(&card, (177, 107), [1, 0, 254]);
+
+    // Unsorted stops are drawn in the order of their positions.
+    let reversed = stops.iter().rev().copied().collect::<Vec<_>>();
+    assert!(render_gradient(GradientShape::Linear { angle: 180.0 }, &reversed) == card);
+}
+
+#[test]
+fn radial_gradients_grow_from_the_center() {
+    let card = render_gradient(GradientShape::Radial, &RED_TO_BLUE);
+    // The center of the card is right of the jacket, in the empty text column.
+    assert_color(&card, (88, 53), [253, 0, 2]);
+    assert_color(&card, (0, 0), [1, 0, 254]);
+    assert_color(&card, (177, 107), [1, 0, 254]);
+}
+
+#[test]
+fn conic_gradients_turn_clockwise_from_their_angle() {
+    let card = render_gradient(GradientShape::Conic { angle: 0.0 }, &RED_TO_BLUE);
+    assert_color(&card, (89, 0), [255, 0, 0]);
+    assert_color(&card, (177, 54), [191, 0, 64]);
+    assert_color(&card, (88, 107), [128, 0, 127]);
+
+    let card = render_gradient(GradientShape::Conic { angle: 90.0 }, &RED_TO_BLUE);
+    assert_color(&card, (177, 54), [255, 0, 0]);
+    assert_color(&card, (88, 107), [191, 0, 64]);
+}
+
+#[test]
+fn background_names_round_trip_and_check_solid_colors() {
+    for background in [
+        Background::Plain,
+        Background::Gradient,
+        Background::Inverted,
+        Background::Custom,
+        Background::BlurredJacket,
+        Background::Solid(String::from("#1db954")),
+    ] {
+        assert_eq!(background.to_string().parse::<Background>(), Ok(background));
+    }
+    assert_eq!("solid-#1DB954".parse(), Ok(Background::Solid(String::from("#1DB954"))));
+
+    for name in ["solid-zz", "solid-1db95", "solid-+1+1+1", "solid-", "sparkles"] {
+        assert!(name.parse::<Background>().is_err(), "{name}");
+    }
+}
@@ -0,0 +1,140 @@
+mod support;
+
+use common::{
+    cards::{
+        generate_canvas_assets, generate_card, generate_card_from_spec, generate_text_assets,
+        CardError,
+    },
+    model::{
+        Background, BrightnessRange, CardData, CardLayout, CardSpec, ContrastOptions,
+        PaletteOptions,
+    },
+};
+use image::{Rgba, RgbaImage};
+use support::{jacket_png, png};
+
+fn card_data(jacket_bytes: Vec<u8>) -> CardData {
+    CardData {
+        track_id: String::from("offline"),
+        name: String::from("Offline Song"),
+        album: String::from("Offline Album"),
+        album_type: String::from("album"),
+        artists: String::from("Nobody"),
+        genres: String::from("#test"),
+        jacket_size: 64,
+        jacket_bytes,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn undecodable_jackets_are_reported() {
+    let card_data = card_data(b"not an image".to_vec());
+
+    let result = generate_card_from_spec(card_data, &CardSpec::default());
+    assert!(matches!(result, Err(CardError::JacketDecode(_))));
+}
+
+#[test]
+fn black_jackets_have_no_palette() {
+    let card_data = card_data(png(RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 255]))));
+
+    let result = generate_card_from_spec(card_data, &CardSpec::default());
+    assert!(matches!(result, Err(CardError::EmptyPalette)));
+}
+
+#[test]
+fn missing_gradients_are_reported() {
+    // Plain colors are still found, but none is bright enough for the gradient.
+    let mut spec = CardSpec {
+        palette: PaletteOptions {
+            gradient_brightness: BrightnessRange { min: 2.99, max: 3.0 },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for background in [Background::Gradient, Background::Custom, Background::BlurredJacket] {
+        spec.background = background;
+        let result = generate_card_from_spec(card_data(jacket_png()), &spec);
+        assert!(matches!(result, Err(CardError::EmptyPalette)), "{}", spec.background);
+    }
+
+    spec.background = Background::Plain;
+    assert!(generate_card_from_spec(card_data(jacket_png()), &spec).is_ok());
+}
+
+#[test]
+fn blank_titles_are_reported() {
+    let card_data = CardData { name: String::from("   "), ..card_data(jacket_png()) };
+
+    let result = generate_card_from_spec(card_data, &CardSpec::default());
+    assert!(matches!(result, Err(CardError::EmptyText(text)) if text == "   "));
+}
+
+#[test]
+fn out_of_bounds_specs_are_rejected() {
+    assert!(CardSpec::default().validate().is_ok());
+
+    let invalid = [
+        CardSpec {
+            layout: CardLayout { jacket_scale: 0.0, ..Default::default() },
+            ..Default::default()
+        },
+        CardSpec {
+            layout: CardLayout { text_scale: f32::NAN, ..Default::default() },
+            ..Default::default()
+        },
+        CardSpec {
+            layout: CardLayout { jacket_offset: u32::MAX, ..Default::default() },
+            ..Default::default()
+        },
+        CardSpec {
+            palette: PaletteOptions { runs: u64::MAX, ..Default::default() },
+            ..Default::default()
+        },
+        CardSpec {
+            palette: PaletteOptions { iterations: 0, ..Default::default() },
+            ..Default::default()
+        },
+        CardSpec {
+            palette: PaletteOptions { sample_size: Some(0), ..Default::default() },
+            ..Default::default()
+        },
+        CardSpec {
+            contrast: ContrastOptions { min_ratio: 30.0, ..Default::default() },
+            ..Default::default()
+        },
+    ];
+    for spec in invalid {
+        assert!(matches!(spec.validate(), Err(CardError::InvalidSpec(_))), "{spec:?}");
+        // Rejected before anything is decoded or allocated.
+        let result = generate_card_from_spec(card_data(b"not an image".to_vec()), &spec);
+        assert!(matches!(result, Err(CardError::InvalidSpec(_))), "{spec:?}");
+    }
+
+    let too_many_colors = CardSpec {
+        palette: PaletteOptions { colors: 1000, ..Default::default() },
+        ..Default::default()
+    };
+    assert!(matches!(too_many_colors.validate(), Err(CardError::InvalidPaletteSize(1000))));
+    let bad_hex =
+        CardSpec { background: Background::Solid(String::from("#zz")), ..Default::default() };
+    assert!(matches!(bad_hex.validate(), Err(CardError::InvalidColor(_))));
+}
+
+#[test]
+fn jackets_without_margin_are_rejected_but_never_underflow() {
+    let spec = CardSpec {
+        layout: CardLayout { jacket_offset: 0, ..Default::default() },
+        ..Default::default()
+    };
+    assert!(matches!(spec.validate(), Err(CardError::InvalidSpec(_))));
+
+    // Drawing from the assets skips the validation, like the web app does.
+    let card_data = CardData { jacket_size: 8, ..card_data(jacket_png()) };
+    let canvas_assets = generate_canvas_assets(card_data.clone(), &spec).unwrap();
+    assert_eq!(canvas_assets.text_offset_x(), 1);
+    let text_assets =
+        generate_text_assets(card_data.clone(), canvas_assets.clone(), &spec).unwrap();
+    assert!(generate_card(card_data, canvas_assets, text_assets, &spec).is_ok());
+}
@@ -0,0 +1,95 @@
+mod support;
+
+use common::{
+    cards::{contrast_ratio, generate_card_from_spec, luminance, text_style},
+    model::{CardData, CardSpec, ContrastFallback, ContrastOptions},
+};
+use image::Rgba;
+use support::jacket_png;
+
+const WHITE: Rgba<u8> = Rgba([255; 4]);
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+#[test]
+fn black_and_white_reach_the_maximum_ratio() {
+    let (white, black) = (luminance(&WHITE), luminance(&BLACK));
+    assert_eq!(white, 1.0);
+    assert_eq!(black, 0.0);
+    assert!((contrast_ratio(white, black) - 21.0).abs() < 1e-4);
+    assert_eq!(contrast_ratio(white, black), contrast_ratio(black, white));
+    assert_eq!(contrast_ratio(white, white), 1.0);
+}
+
+#[test]
+fn uniform_backgrounds_need_no_fallback() {
+    let options = ContrastOptions::default();
+
+    let style = text_style(&[0.0; 100], &options);
+    assert_eq!(style.color, WHITE);
+    assert_eq!(style.fallback, None);
+
+    let style = text_style(&[1.0; 100], &options);
+    assert_eq!(style.color, BLACK);
+    assert_eq!(style.fallback, None);
+}
+
+#[test]
+fn busy_backgrounds_trigger_the_fallback() {
+    // Half black and half white, so either text color disappears on half of the pixels.
+    let backdrop = [0.0, 1.0].repeat(50);
+
+    let style = text_style(&backdrop, &ContrastOptions::default());
+    assert_eq!(style.fallback, Some(ContrastFallback::Backdrop));
+
+    let options = ContrastOptions { fallback: ContrastFallback::Outline, ..Default::default() };
+    assert_eq!(text_style(&backdrop, &options).fallback, Some(ContrastFallback::Outline));
+}
+
+#[test]
+fn minimum_ratio_is_respected() {
+    // Mid grey, against which black reaches about 5.3 and white about 3.9.
+    let grey = [luminance(&Rgba([128, 128, 128, 255])); 100];
+
+    let style = text_style(&grey, &ContrastOptions { min_ratio: 4.5, ..Default::default() });
+    assert_eq!(style.color, BLACK);
+    assert_eq!(style.fallback, None);
+
+    let style = text_style(&grey, &ContrastOptions { min_ratio: 7.0, ..Default::default() });
+    assert_eq!(style.color, BLACK);
+    assert_eq!(style.fallback, Some(ContrastFallback::Backdrop));
+
+    // Even a busy background is readable enough when any ratio is accepted.
+    let busy = [0.0, 1.0].repeat(50);
+    let style = text_style(&busy, &ContrastOptions { min_ratio: 1.0, ..Default::default() });
+    assert_eq!(style.fallback, None);
+}
+
+fn render(contrast: ContrastOptions) -> Vec<u8> {
+    let card_data = CardData {
+        track_id: String::from("offline"),
+        name: String::from("Offline Song"),
+        album: String::from("Offline Album"),
+        album_type: String::from("album"),
+        artists: String::from("Nobody"),
+        genres: String::from("#test"),
+        jacket_size: 64,
+        jacket_bytes: jacket_png(),
+        ..Default::default()
+    };
+
+    generate_card_from_spec(card_data, &CardSpec { contrast, ..Default::default() }).unwrap()
+}
+
+#[test]
+fn fallbacks_are_drawn_when_the_ratio_is_not_reached() {
+    let plain = render(ContrastOptions { min_ratio: 1.0, ..Default::default() });
+    // No text reaches a ratio of 21 on a colorful card, so every text gets the fallback.
+    let fallbacks =
+        [ContrastFallback::Outline, ContrastFallback::Shadow, ContrastFallback::Backdrop]
+            .map(|fallback| render(ContrastOptions { min_ratio: 21.0, fallback }));
+
+    for (i, card) in fallbacks.iter().enumerate() {
+        assert!(*card != plain, "fallback {i} is not drawn");
+        assert!(fallbacks[i + 1..].iter().all(|other| other != card));
+    }
+}
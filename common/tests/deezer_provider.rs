@@ -0,0 +1,110 @@
+#![cfg(feature = "http")]
+
+mod support;
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Router,
+};
+use common::{
+    model::CardKind,
+    providers::{deezer::DeezerProvider, MusicProvider, ProviderError},
+};
+use support::{fixture, jacket_png, serve_with_url, BaseUrl};
+
+const TRACK_ID: &str = "3135556";
+
+async fn stub_track(State(base_url): State<BaseUrl>, Path(id): Path<String>) -> String {
+    match id.as_str() {
+        TRACK_ID => fixture("deezer/track.json", base_url.get().unwrap()),
+        _ => fixture("deezer/no_data.json", ""),
+    }
+}
+
+async fn stub_album(State(base_url): State<BaseUrl>) -> String {
+    fixture("deezer/album.json", base_url.get().unwrap())
+}
+
+async fn provider() -> DeezerProvider {
+    let base_url = serve_with_url(|base_url| {
+        Router::new()
+            .route("/track/:id", get(stub_track))
+            .route("/album/302127", get(stub_album))
+            .route("/images/cover/*path", get(|| async { jacket_png() }))
+            .with_state(base_url)
+    })
+    .await;
+    DeezerProvider::new().with_api_url(&base_url)
+}
+
+#[tokio::test]
+async fn tracks_are_mapped_into_card_data() {
+    let card_data = provider().await.track(TRACK_ID).await.unwrap();
+
+    assert_eq!(card_data.track_id, TRACK_ID);
+    assert_eq!(card_data.name, "Harder, Better, Faster, Stronger");
+    assert_eq!(card_data.album, "Discovery");
+    assert_eq!(card_data.album_type, "album");
+    assert_eq!(card_data.artists, "Daft Punk");
+    assert_eq!(card_data.genres, "#dance #electro");
+    // Width of the served jacket, which the provider does not report.
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+}
+
+#[tokio::test]
+async fn albums_are_mapped_into_card_data() {
+    let card_data = provider().await.card("album:302127").await.unwrap();
+
+    assert_eq!(card_data.track_id, "album:302127");
+    assert_eq!(card_data.kind, CardKind::Album);
+    assert_eq!(card_data.name, "Discovery");
+    assert_eq!(card_data.album, "Discovery");
+    assert_eq!(card_data.album_type, "album");
+    assert_eq!(card_data.artists, "Daft Punk");
+    assert_eq!(card_data.genres, "#dance #electro");
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+    assert_eq!(card_data.release_date.as_deref(), Some("2001-03-07"));
+    assert_eq!(card_data.total_tracks, Some(14));
+    assert_eq!(card_data.duration_ms, Some(3_653_000));
+    assert_eq!(card_data.label.as_deref(), Some("Parlophone (France)"));
+    assert_eq!(card_data.external_url.as_deref(), Some("https://www.deezer.com/album/302127"));
+}
+
+#[tokio::test]
+async fn unknown_tracks_are_not_found() {
+    // Deezer answers with an error object and a 200 status.
+    let result = provider().await.track("1").await;
+    assert!(matches!(result, Err(ProviderError::NotFound(_))), "{result:?}");
+}
+
+#[test]
+fn track_ids_are_parsed_from_links() {
+    let provider = DeezerProvider::new();
+
+    for input in [
+        "https://www.deezer.com/track/3135556",
+        "https://www.deezer.com/en/track/3135556",
+        "https://www.deezer.com/pt-br/track/3135556?utm_source=deezer&utm_content=track-3135556",
+        "https://deezer.com/track/3135556",
+        "deezer:track:3135556",
+    ] {
+        assert_eq!(provider.parse_track_id(input).as_deref(), Some(TRACK_ID), "{input}");
+    }
+    for input in [
+        "https://www.deezer.com/album/302127",
+        "https://www.deezer.com/fr/album/302127?utm_source=deezer",
+        "deezer:album:302127",
+    ] {
+        assert_eq!(provider.parse_track_id(input).as_deref(), Some("album:302127"), "{input}");
+    }
+    for input in [
+        "https://www.deezer.com/artist/27",
+        "https://open.spotify.com/track/7GbcnXvXZTj0bBQkfzpvGK",
+        "3135556",
+    ] {
+        assert_eq!(provider.parse_track_id(input), None, "{input}");
+    }
+}
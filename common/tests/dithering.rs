@@ -0,0 +1,81 @@
+mod support;
+
+use common::{
+    cards::generate_card_from_spec,
+    model::{
+        Background, CardColors, CardData, CardFields, CardSpec, ColorStop, Dithering,
+        GradientShape,
+    },
+};
+use image::{DynamicImage, GenericImageView};
+use support::jacket_png;
+
+/// Renders an empty card over a vertical gradient between two very close grays.
+fn render_close_gradient(dithering: Dithering) -> DynamicImage {
+    let card_data = CardData {
+        track_id: String::from("test"),
+        name: String::new(),
+        album: String::new(),
+        album_type: String::from("album"),
+        artists: String::new(),
+        genres: String::new(),
+        jacket_size: 64,
+        jacket_bytes: jacket_png(),
+        ..Default::default()
+    };
+    let stop = |color: &str, position| ColorStop { color: color.to_owned(), position };
+    let spec = CardSpec {
+        background: Background::Custom,
+        gradient: GradientShape::Linear { angle: 180.0 },
+        dithering,
+        colors: CardColors { custom_stops: Some(vec![stop("#202020", 0.0), stop("#282828", 1.0)]) },
+        fields: CardFields {
+            name: false,
+            album: false,
+            artists: false,
+            genres: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    image::load_from_memory(&generate_card_from_spec(card_data, &spec).unwrap()).unwrap()
+}
+
+/// Mean red channel of every row, over an 8 pixel wide strip on the right edge of the card.
+fn row_means(card: &DynamicImage) -> Vec<u32> {
+    let strip = (card.width() / 8 - 1) * 8..card.width() / 8 * 8;
+    (0..card.height())
+        .map(|y| strip.clone().map(|x| card.get_pixel(x, y)[0] as u32).sum())
+        .collect()
+}
+
+/// Height of the tallest run of rows that look identical, i.e. the widest visible band.
+fn widest_band(card: &DynamicImage) -> usize {
+    row_means(card)
+        .chunk_by(|a, b| a == b)
+        .map(|band| band.len())
+        .max()
+        .unwrap_or_default()
+}
+
+#[test]
+fn bayer_dithering_breaks_up_gradient_bands() {
+    let plain = render_close_gradient(Dithering::None);
+    let dithered = render_close_gradient(Dithering::Bayer);
+
+    // 8 levels spread over the whole card leave bands several pixels tall.
+    assert!(widest_band(&plain) >= plain.height() as usize / 16);
+    assert!(widest_band(&dithered) <= 8);
+}
+
+#[test]
+fn bayer_dithering_preserves_average_color() {
+    let plain = render_close_gradient(Dithering::None);
+    let dithered = render_close_gradient(Dithering::Bayer);
+    let average = |card: &DynamicImage| {
+        row_means(card).iter().sum::<u32>() as f32 / (card.height() * 8) as f32
+    };
+
+    assert!((average(&plain) - average(&dithered)).abs() < 0.5);
+}
@@ -0,0 +1,110 @@
+use common::{
+    cards::{find_best_colors, CardError},
+    colors::{KMeans, MedianCut, Octree, PaletteExtractor},
+    model::PaletteOptions,
+};
+use kmeans_colors::CentroidData;
+use palette::{IntoColor, Lab, Srgb};
+
+const EXTRACTORS: [(&str, &dyn PaletteExtractor); 2] =
+    [("median cut", &MedianCut), ("octree", &Octree)];
+
+fn options(colors: usize) -> PaletteOptions {
+    PaletteOptions { colors, ..Default::default() }
+}
+
+/// 4096 pixels, each with a different color.
+fn busy_pixels() -> Vec<Srgb<u8>> {
+    (0..4096u32)
+        .map(|i| Srgb::new((i % 16 * 16) as u8, (i / 16 % 16 * 16) as u8, (i / 256 * 16) as u8))
+        .collect()
+}
+
+fn rgb(centroid: &CentroidData<Lab>) -> [u8; 3] {
+    let srgb: Srgb = centroid.centroid.into_color();
+    let srgb = srgb.into_format::<u8>();
+    [srgb.red, srgb.green, srgb.blue]
+}
+
+fn is_close(a: [u8; 3], b: [u8; 3]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 1)
+}
+
+#[test]
+fn extractors_return_at_most_the_requested_colors() {
+    let pixels = busy_pixels();
+    for (name, extractor) in EXTRACTORS {
+        for colors in [1, 3, 8, 64] {
+            let centroids = extractor.extract(&pixels, &options(colors));
+            assert!(!centroids.is_empty(), "{name} with {colors} colors");
+            assert!(centroids.len() <= colors, "{name} returned {} colors", centroids.len());
+
+            let total = centroids.iter().map(|c| c.percentage).sum::<f32>();
+            assert!((total - 1.0).abs() < 1e-4, "{name} covers {total} of the pixels");
+            assert!(centroids.windows(2).all(|w| w[0].centroid.l <= w[1].centroid.l), "{name}");
+        }
+    }
+}
+
+#[test]
+fn solid_colors_yield_a_single_centroid() {
+    let pixels = vec![Srgb::new(200u8, 40, 40); 256];
+    for (name, extractor) in EXTRACTORS {
+        let centroids = extractor.extract(&pixels, &options(8));
+        assert_eq!(centroids.len(), 1, "{name}");
+        assert!(is_close(rgb(&centroids[0]), [200, 40, 40]), "{name}: {:?}", rgb(&centroids[0]));
+        assert_eq!(centroids[0].percentage, 1.0, "{name}");
+    }
+}
+
+#[test]
+fn two_colors_are_told_apart() {
+    let dark = Srgb::new(20u8, 30, 120);
+    let bright = Srgb::new(230u8, 200, 40);
+    for (name, extractor) in EXTRACTORS {
+        for (dark_pixels, colors) in [(128, 2), (128, 8), (64, 8)] {
+            let mut pixels = vec![dark; dark_pixels];
+            pixels.extend(vec![bright; 256 - dark_pixels]);
+
+            let centroids = extractor.extract(&pixels, &options(colors));
+            assert_eq!(centroids.len(), 2, "{name} with {colors} colors");
+            // From darkest to brightest.
+            assert!(is_close(rgb(&centroids[0]), [20, 30, 120]), "{name}");
+            assert_eq!(centroids[0].percentage, dark_pixels as f32 / 256.0, "{name}");
+            assert!(is_close(rgb(&centroids[1]), [230, 200, 40]), "{name}");
+            assert_eq!(centroids[1].percentage, 1.0 - dark_pixels as f32 / 256.0, "{name}");
+
+            let merged = extractor.extract(&pixels, &options(1));
+            assert_eq!(merged.len(), 1, "{name}");
+            assert_eq!(merged[0].percentage, 1.0, "{name}");
+        }
+    }
+}
+
+#[test]
+fn extractors_are_deterministic() {
+    let pixels = busy_pixels();
+    for (name, extractor) in EXTRACTORS {
+        let first = extractor.extract(&pixels, &options(5));
+        for _ in 0..8 {
+            let again = extractor.extract(&pixels, &options(5));
+            let same = first.iter().zip(&again).all(|(a, b)| {
+                a.centroid == b.centroid && a.percentage == b.percentage && a.index == b.index
+            });
+            assert!(same && first.len() == again.len(), "{name}");
+        }
+    }
+}
+
+#[test]
+fn palette_sizes_are_checked_for_every_extractor() {
+    let pixels = busy_pixels();
+    let extractors: [&dyn PaletteExtractor; 3] = [&KMeans, &MedianCut, &Octree];
+    for extractor in extractors {
+        for colors in [0, 257] {
+            let result = find_best_colors(&pixels, extractor, &options(colors));
+            assert!(matches!(result, Err(CardError::InvalidPaletteSize(c)) if c == colors));
+        }
+        assert!(find_best_colors(&pixels, extractor, &options(256)).is_ok());
+    }
+}
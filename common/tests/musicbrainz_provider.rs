@@ -0,0 +1,170 @@
+#![cfg(feature = "http")]
+
+mod support;
+
+use axum::{
+    extract::Path,
+    http::{header::USER_AGENT, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use common::providers::{musicbrainz::MusicBrainzProvider, MusicProvider, ProviderError};
+use support::{fixture, jacket_png, serve};
+
+const RECORDING_ID: &str = "c3f7b8a2-5d1e-4c9b-8e2f-1a6d4b7c9e30";
+const RELEASE_ID: &str = "1a9c5e3d-6b2f-4a8e-9d7c-3e5f7a9b1c24";
+const ISRC: &str = "GBDUW0000059";
+/// Release group of Discovery, whose releases have no cover art of their own in the stub.
+const DISCOVERY_GROUP_ID: &str = "48117b82-8f9c-4d95-9a5c-6b3a2e5e0b4a";
+
+fn found(path: &str) -> Response {
+    fixture(path, "").into_response()
+}
+
+fn not_found() -> Response {
+    (StatusCode::NOT_FOUND, fixture("musicbrainz/not_found.json", "")).into_response()
+}
+
+async fn stub_recording(Path(id): Path<String>) -> Response {
+    match id.as_str() {
+        RECORDING_ID => found("musicbrainz/recording.json"),
+        _ => not_found(),
+    }
+}
+
+async fn stub_release(Path(id): Path<String>) -> Response {
+    match id.as_str() {
+        RELEASE_ID => found("musicbrainz/release.json"),
+        _ => not_found(),
+    }
+}
+
+async fn stub_isrc(Path(isrc): Path<String>) -> Response {
+    match isrc.as_str() {
+        ISRC => found("musicbrainz/isrc.json"),
+        _ => not_found(),
+    }
+}
+
+async fn stub_release_cover(Path(id): Path<String>) -> Response {
+    match id.as_str() {
+        RELEASE_ID => jacket_png().into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn stub_release_group_cover(Path(id): Path<String>) -> Response {
+    match id.as_str() {
+        DISCOVERY_GROUP_ID => jacket_png().into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// MusicBrainz turns away clients without a meaningful user agent.
+async fn require_user_agent<B>(request: Request<B>, next: Next<B>) -> Response {
+    let user_agent = request.headers().get(USER_AGENT).and_then(|value| value.to_str().ok());
+    match user_agent {
+        Some(user_agent) if user_agent.starts_with("custom-spotify-cards/") => {
+            next.run(request).await
+        }
+        _ => StatusCode::FORBIDDEN.into_response(),
+    }
+}
+
+async fn provider() -> MusicBrainzProvider {
+    let api_url = serve(
+        Router::new()
+            .route("/recording/:id", get(stub_recording))
+            .route("/release/:id", get(stub_release))
+            .route("/isrc/:isrc", get(stub_isrc))
+            .layer(middleware::from_fn(require_user_agent)),
+    )
+    .await;
+    let cover_art_url = serve(
+        Router::new()
+            .route("/release/:id/front-500", get(stub_release_cover))
+            .route("/release-group/:id/front-500", get(stub_release_group_cover)),
+    )
+    .await;
+    MusicBrainzProvider::new().with_api_url(&api_url).with_cover_art_url(&cover_art_url)
+}
+
+#[tokio::test]
+async fn recordings_are_mapped_into_card_data() {
+    let card_data = provider().await.track(RECORDING_ID).await.unwrap();
+
+    assert_eq!(card_data.track_id, RECORDING_ID);
+    assert_eq!(card_data.name, "Harder, Better, Faster, Stronger");
+    // The earliest dated official album wins over singles, compilations and bootlegs.
+    assert_eq!(card_data.album, "Discovery");
+    assert_eq!(card_data.album_type, "album");
+    assert_eq!(card_data.artists, "Daft Punk");
+    assert_eq!(card_data.genres, "#french house #house #electronic #dance");
+    // Width of the served jacket, which the provider does not report.
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+}
+
+#[tokio::test]
+async fn releases_resolve_to_their_first_track() {
+    let card_data = provider().await.track(&format!("release:{RELEASE_ID}")).await.unwrap();
+
+    assert_eq!(card_data.track_id, "d9e8f7a6-b5c4-4d3e-8f2a-1b0c9d8e7f65");
+    assert_eq!(card_data.name, "Harder, Better, Faster, Stronger (radio edit)");
+    assert_eq!(card_data.album, "Harder, Better, Faster, Stronger");
+    assert_eq!(card_data.album_type, "single");
+    assert_eq!(card_data.artists, "Daft Punk");
+    assert_eq!(card_data.genres, "#french house");
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+}
+
+#[tokio::test]
+async fn isrcs_resolve_to_their_recording() {
+    let card_data = provider().await.track(&format!("isrc:{ISRC}")).await.unwrap();
+    assert_eq!(card_data.track_id, RECORDING_ID);
+}
+
+#[tokio::test]
+async fn unknown_ids_are_not_found() {
+    let provider = provider().await;
+
+    for id in [
+        "00000000-0000-0000-0000-000000000000",
+        "release:00000000-0000-0000-0000-000000000000",
+        "isrc:USAAA0000000",
+    ] {
+        let result = provider.track(id).await;
+        assert!(matches!(result, Err(ProviderError::NotFound(_))), "{id}: {result:?}");
+    }
+    let result = provider.track("label:00000000-0000-0000-0000-000000000000").await;
+    assert!(matches!(result, Err(ProviderError::InvalidId(_))), "{result:?}");
+}
+
+#[test]
+fn track_ids_are_parsed_from_links() {
+    let provider = MusicBrainzProvider::new();
+
+    for (input, id) in [
+        (format!("https://musicbrainz.org/recording/{RECORDING_ID}"), RECORDING_ID.to_owned()),
+        (
+            format!("https://beta.musicbrainz.org/recording/{RECORDING_ID}/tags"),
+            RECORDING_ID.to_owned(),
+        ),
+        (RECORDING_ID.to_owned(), RECORDING_ID.to_owned()),
+        (format!("https://musicbrainz.org/release/{RELEASE_ID}"), format!("release:{RELEASE_ID}")),
+        (format!("release:{RELEASE_ID}"), format!("release:{RELEASE_ID}")),
+        (ISRC.to_owned(), format!("isrc:{ISRC}")),
+        (format!("isrc:{ISRC}"), format!("isrc:{ISRC}")),
+    ] {
+        assert_eq!(provider.parse_track_id(&input), Some(id), "{input}");
+    }
+    for input in [
+        format!("https://musicbrainz.org/artist/{RECORDING_ID}"),
+        "https://open.spotify.com/track/7GbcnXvXZTj0bBQkfzpvGK".to_owned(),
+        "3135556".to_owned(),
+    ] {
+        assert_eq!(provider.parse_track_id(&input), None, "{input}");
+    }
+}
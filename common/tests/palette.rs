@@ -0,0 +1,99 @@
+use common::{
+    cards::{find_best_colors, jacket_pixels, CardError},
+    colors::{KMeans, MedianCut, Octree, PaletteExtractor},
+    model::PaletteOptions,
+};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Jacket with a red top half and a gray bottom half.
+fn jacket() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |_, y| {
+        if y < 16 {
+            Rgba([200, 40, 40, 255])
+        } else {
+            Rgba([120, 120, 120, 255])
+        }
+    }))
+}
+
+fn is_close(a: &Rgba<u8>, b: &Rgba<u8>) -> bool {
+    a.0.iter().zip(b.0).all(|(a, b)| a.abs_diff(b) <= 3)
+}
+
+fn colors_of(jacket: &DynamicImage) -> Vec<Rgba<u8>> {
+    find_best_colors(&jacket_pixels(jacket), &KMeans, &PaletteOptions::default())
+        .unwrap()
+        .all_colors
+}
+
+#[test]
+fn every_color_type_yields_the_same_palette() {
+    let jacket = jacket();
+    let expected = colors_of(&jacket);
+    assert!(expected.iter().any(|c| is_close(c, &Rgba([200, 40, 40, 255]))));
+
+    let variants = [
+        DynamicImage::ImageRgb8(jacket.to_rgb8()),
+        DynamicImage::ImageRgba8(jacket.to_rgba8()),
+        DynamicImage::ImageRgb16(jacket.to_rgb16()),
+        DynamicImage::ImageRgba16(jacket.to_rgba16()),
+        DynamicImage::ImageRgb32F(jacket.to_rgb32f()),
+        DynamicImage::ImageRgba32F(jacket.to_rgba32f()),
+    ];
+    for variant in variants {
+        let colors = colors_of(&variant);
+        assert_eq!(colors.len(), expected.len(), "{:?}", variant.color());
+        assert!(colors.iter().zip(&expected).all(|(a, b)| is_close(a, b)), "{:?}", variant.color());
+    }
+}
+
+// There is no CMYK `DynamicImage`: the JPEG decoder of `image` converts CMYK covers to RGB while
+// decoding them, so they reach `jacket_pixels` as `ImageRgb8` and are covered by the test above.
+
+#[test]
+fn grayscale_jackets_yield_gray_colors() {
+    let jacket = jacket();
+    let variants = [
+        DynamicImage::ImageLuma8(jacket.to_luma8()),
+        DynamicImage::ImageLumaA8(jacket.to_luma_alpha8()),
+        DynamicImage::ImageLuma16(jacket.to_luma16()),
+        DynamicImage::ImageLumaA16(jacket.to_luma_alpha16()),
+    ];
+    for variant in variants {
+        let colors = colors_of(&variant);
+        assert!(!colors.is_empty(), "{:?}", variant.color());
+        assert!(
+            colors.iter().all(|c| c[0].abs_diff(c[1]) <= 2 && c[1].abs_diff(c[2]) <= 2),
+            "{:?}",
+            variant.color()
+        );
+    }
+}
+
+#[test]
+fn transparent_pixels_are_ignored() {
+    let jacket = DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, _| {
+        if x < 24 {
+            Rgba([30, 60, 230, 0])
+        } else {
+            Rgba([200, 40, 40, 255])
+        }
+    }));
+
+    let colors = colors_of(&jacket);
+    assert!(!colors.is_empty());
+    assert!(colors.iter().all(|c| is_close(c, &Rgba([200, 40, 40, 255]))));
+}
+
+#[test]
+fn fully_transparent_jackets_have_no_palette() {
+    let jacket = DynamicImage::ImageRgba8(RgbaImage::from_pixel(32, 32, Rgba([200, 40, 40, 0])));
+    let pixels = jacket_pixels(&jacket);
+    assert!(pixels.is_empty());
+
+    let extractors: [&dyn PaletteExtractor; 3] = [&KMeans, &MedianCut, &Octree];
+    for extractor in extractors {
+        let result = find_best_colors(&pixels, extractor, &PaletteOptions::default());
+        assert!(matches!(result, Err(CardError::EmptyPalette)));
+    }
+}
@@ -0,0 +1,150 @@
+mod support;
+
+use common::{
+    cards::{generate_card_from_spec, mosaic_jacket},
+    model::{CardData, CardFields, CardKind, CardSpec, ListedTrack},
+    providers::{FakeProvider, MusicProvider, ProviderError},
+};
+use support::jacket_png;
+
+fn card_data(track_id: &str) -> CardData {
+    CardData {
+        track_id: track_id.to_owned(),
+        name: String::from("Offline Song"),
+        album: String::from("Offline Album"),
+        album_type: String::from("album"),
+        artists: String::from("Nobody"),
+        genres: String::from("#test"),
+        jacket_size: 64,
+        jacket_bytes: jacket_png(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn fake_tracks_render_into_cards() {
+    let provider = FakeProvider::new().with_track(card_data("offline"));
+
+    let id = provider.parse_track_id("offline").unwrap();
+    let track = provider.track(&id).await.unwrap();
+    assert_eq!(track, card_data("offline"));
+
+    let card = generate_card_from_spec(track, &CardSpec::default()).unwrap();
+    assert!(image::load_from_memory(&card).is_ok());
+}
+
+#[tokio::test]
+async fn fake_provider_only_knows_its_tracks() {
+    let provider = FakeProvider::new().with_track(card_data("offline"));
+
+    assert_eq!(provider.parse_track_id("elsewhere"), None);
+    assert!(matches!(provider.track("elsewhere").await, Err(ProviderError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn fake_albums_render_into_album_cards() {
+    let album = CardData {
+        kind: CardKind::Album,
+        release_date: Some(String::from("2001-03-07")),
+        total_tracks: Some(14),
+        duration_ms: Some(3_653_000),
+        label: Some(String::from("Offline Records")),
+        ..card_data("album:offline")
+    };
+    let provider = FakeProvider::new().with_track(album.clone());
+
+    let card_data = provider.card("album:offline").await.unwrap();
+    assert_eq!(card_data, album);
+    assert!(matches!(provider.card("offline").await, Err(ProviderError::NotFound(_))));
+
+    let album_card = generate_card_from_spec(card_data.clone(), &CardSpec::default()).unwrap();
+    let track_card = generate_card_from_spec(
+        CardData { kind: CardKind::Track, ..card_data },
+        &CardSpec::default(),
+    )
+    .unwrap();
+    assert!(album_card != track_card, "album details are drawn");
+}
+
+#[test]
+fn mosaics_tile_up_to_nine_covers() {
+    assert_eq!(mosaic_jacket(&vec![jacket_png(); 3], 640).unwrap(), None);
+    for covers in [4, 8, 9, 12] {
+        let mosaic = mosaic_jacket(&vec![jacket_png(); covers], 640).unwrap().unwrap();
+        let mosaic = image::load_from_memory(&mosaic).unwrap();
+        assert_eq!((mosaic.width(), mosaic.height()), (640, 640), "{covers} covers");
+    }
+}
+
+#[tokio::test]
+async fn fake_playlists_render_into_playlist_cards() {
+    let jacket = mosaic_jacket(&vec![jacket_png(); 4], 640).unwrap().unwrap();
+    let playlist = CardData {
+        kind: CardKind::Playlist,
+        jacket_size: 640,
+        jacket_bytes: jacket,
+        total_tracks: Some(40),
+        owner: Some(String::from("Nobody")),
+        followers: Some(1234),
+        tracklist: (1..=12)
+            .map(|i| ListedTrack { name: format!("Song {i}"), artists: String::from("Nobody") })
+            .collect(),
+        ..card_data("playlist:offline")
+    };
+    let provider = FakeProvider::new().with_track(playlist.clone());
+
+    let card_data = provider.card("playlist:offline").await.unwrap();
+    assert_eq!(card_data, playlist);
+
+    let mut spec = CardSpec::default();
+    let card = generate_card_from_spec(card_data.clone(), &spec).unwrap();
+    spec.fields.tracklist = false;
+    let without_tracklist = generate_card_from_spec(card_data, &spec).unwrap();
+    assert!(card != without_tracklist, "tracks are listed");
+}
+
+#[test]
+fn track_metadata_can_be_toggled() {
+    let bare = CardData { jacket_size: 640, ..card_data("offline") };
+    let track = CardData {
+        release_date: Some(String::from("2019-05-03")),
+        total_tracks: Some(12),
+        track_number: Some(4),
+        duration_ms: Some(222_000),
+        explicit: true,
+        ..bare.clone()
+    };
+    let render = |card_data: &CardData, fields: CardFields| {
+        let spec = CardSpec { fields, ..Default::default() };
+        generate_card_from_spec(card_data.clone(), &spec).unwrap()
+    };
+
+    // The duration and year are shown by default, the track number and explicit badge are not.
+    let card = render(&track, CardFields::default());
+    let toggled = [
+        ("duration", CardFields { duration: false, ..Default::default() }),
+        ("release_year", CardFields { release_year: false, ..Default::default() }),
+        ("track_number", CardFields { track_number: true, ..Default::default() }),
+        ("explicit", CardFields { explicit: true, ..Default::default() }),
+    ]
+    .map(|(field, fields)| (field, render(&track, fields)));
+    for (i, (field, toggled_card)) in toggled.iter().enumerate() {
+        assert!(*toggled_card != card, "{field} is not toggled");
+        for (other, other_card) in &toggled[i + 1..] {
+            assert!(other_card != toggled_card, "{field} and {other} draw the same card");
+        }
+    }
+
+    // Fields the track does not have draw nothing, even when turned on.
+    let clean = CardData { track_number: None, explicit: false, ..track.clone() };
+    for fields in [
+        CardFields { track_number: true, ..Default::default() },
+        CardFields { explicit: true, ..Default::default() },
+    ] {
+        assert!(render(&clean, fields) == render(&clean, CardFields::default()));
+    }
+
+    let hidden = CardFields { duration: false, release_year: false, ..Default::default() };
+    let bare_card = render(&bare, CardFields::default());
+    assert!(render(&track, hidden) == bare_card, "hidden metadata leaves no room");
+}
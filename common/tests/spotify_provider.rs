@@ -0,0 +1,401 @@
+#![cfg(feature = "http")]
+
+mod support;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use common::{
+    model::{AccessToken, CardKind, ListedTrack},
+    providers::{
+        spotify::{SpotifyProvider, TokenSource},
+        MusicProvider, ProviderError, JACKET_SIZE,
+    },
+};
+use support::{fixture, jacket_png, serve_with_url, BaseUrl};
+
+const TRACK_ID: &str = "7GbcnXvXZTj0bBQkfzpvGK";
+const ARTIST_ID: &str = "0oSGxfWSnnOXhD2fKuz2Gy";
+const ALBUM_ID: &str = "6fQElzBNTiEMGdIeY0hy5l";
+const PLAYLIST_ID: &str = "37i9dQZF1DXcBWIGoYBM5M";
+const EPISODE_ID: &str = "512ojhOuo1ktJprKbVcKyQ";
+const SHOW_ID: &str = "5CfCWKI5pZ28U0uOzXkDHe";
+
+/// Hands out `token-1`, `token-2`... counting how often it was asked.
+#[derive(Clone, Default)]
+struct CountingTokens(Arc<AtomicUsize>);
+
+#[async_trait]
+impl TokenSource for CountingTokens {
+    async fn token(&self) -> Result<AccessToken, ProviderError> {
+        let count = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(AccessToken {
+            access_token: format!("token-{count}"),
+            token_type: String::from("Bearer"),
+            expires_in: 3600,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct Stub {
+    base_url: BaseUrl,
+    /// Token the stub accepts, older ones are answered as expired.
+    valid_token: &'static str,
+}
+
+impl Stub {
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        let expected = format!("Bearer {}", self.valid_token);
+        headers.get(header::AUTHORIZATION).is_some_and(|value| value == expected.as_str())
+    }
+}
+
+async fn stub_track(
+    State(stub): State<Stub>, Path(id): Path<String>, headers: HeaderMap,
+) -> impl IntoResponse {
+    if !stub.authorized(&headers) {
+        return (StatusCode::UNAUTHORIZED, String::from("The access token expired"));
+    }
+    if id != TRACK_ID {
+        return (StatusCode::NOT_FOUND, String::from("non existing id"));
+    }
+    (StatusCode::OK, fixture("spotify/track.json", stub.base_url.get().unwrap()))
+}
+
+async fn stub_artist(
+    State(stub): State<Stub>, Path(id): Path<String>, headers: HeaderMap,
+) -> impl IntoResponse {
+    if !stub.authorized(&headers) || id != ARTIST_ID {
+        return (StatusCode::UNAUTHORIZED, String::new());
+    }
+    (StatusCode::OK, fixture("spotify/artist.json", stub.base_url.get().unwrap()))
+}
+
+async fn stub_album(
+    State(stub): State<Stub>, Path(id): Path<String>, headers: HeaderMap,
+) -> impl IntoResponse {
+    if !stub.authorized(&headers) || id != ALBUM_ID {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    (StatusCode::OK, fixture("spotify/album.json", stub.base_url.get().unwrap()))
+}
+
+async fn stub_album_tracks(
+    State(stub): State<Stub>, Path(id): Path<String>, Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    // The album embeds the first 8 tracks, the stub only knows the page after them.
+    if !stub.authorized(&headers)
+        || id != ALBUM_ID
+        || query.get("offset").map(String::as_str) != Some("8")
+    {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    (StatusCode::OK, fixture("spotify/album_tracks.json", ""))
+}
+
+async fn stub_playlist(
+    State(stub): State<Stub>, Path(id): Path<String>, headers: HeaderMap,
+) -> impl IntoResponse {
+    if !stub.authorized(&headers) || id != PLAYLIST_ID {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    (StatusCode::OK, fixture("spotify/playlist.json", stub.base_url.get().unwrap()))
+}
+
+async fn stub_top_tracks(
+    State(stub): State<Stub>, Path(id): Path<String>, Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !stub.authorized(&headers) || id != ARTIST_ID {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    // Top tracks depend on the country and the Web API refuses to guess it.
+    if !query.contains_key("market") {
+        return (StatusCode::BAD_REQUEST, String::from("Missing market"));
+    }
+    (StatusCode::OK, fixture("spotify/top_tracks.json", stub.base_url.get().unwrap()))
+}
+
+/// Podcasts are only found for a market, like top tracks.
+fn podcast(
+    stub: &Stub, query: &HashMap<String, String>, headers: &HeaderMap, path: &str,
+) -> (StatusCode, String) {
+    if !stub.authorized(headers) || !query.contains_key("market") {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    (StatusCode::OK, fixture(path, stub.base_url.get().unwrap()))
+}
+
+async fn stub_episode(
+    State(stub): State<Stub>, Path(id): Path<String>, Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if id != EPISODE_ID {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    podcast(&stub, &query, &headers, "spotify/episode.json")
+}
+
+async fn stub_show(
+    State(stub): State<Stub>, Path(id): Path<String>, Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if id != SHOW_ID {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    podcast(&stub, &query, &headers, "spotify/show.json")
+}
+
+async fn start(valid_token: &'static str) -> String {
+    serve_with_url(|base_url| {
+        Router::new()
+            .route("/v1/tracks/:id", get(stub_track))
+            .route("/v1/artists/:id", get(stub_artist))
+            .route("/v1/artists/:id/top-tracks", get(stub_top_tracks))
+            .route("/v1/albums/:id", get(stub_album))
+            .route("/v1/albums/:id/tracks", get(stub_album_tracks))
+            .route("/v1/playlists/:id", get(stub_playlist))
+            .route("/v1/episodes/:id", get(stub_episode))
+            .route("/v1/shows/:id", get(stub_show))
+            .route("/image/:id", get(|| async { jacket_png() }))
+            .with_state(Stub { base_url, valid_token })
+    })
+    .await
+}
+
+#[tokio::test]
+async fn tracks_are_mapped_into_card_data() {
+    let base_url = start("token-1").await;
+    let provider =
+        SpotifyProvider::new(CountingTokens::default()).with_api_url(&format!("{base_url}/v1"));
+
+    let card_data = provider.track(TRACK_ID).await.unwrap();
+    assert_eq!(card_data.track_id, TRACK_ID);
+    assert_eq!(card_data.name, "Life on Mars? - 2015 Remaster");
+    assert_eq!(card_data.album, "Hunky Dory (2015 Remaster)");
+    assert_eq!(card_data.album_type, "album");
+    assert_eq!(card_data.artists, "David Bowie");
+    assert_eq!(card_data.genres, "#art rock #classic rock #glam rock #permanent wave #rock");
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+    assert_eq!(card_data.release_date.as_deref(), Some("1971-12-17"));
+    assert_eq!(card_data.total_tracks, Some(11));
+    assert_eq!(card_data.duration_ms, Some(207_186));
+    assert!(!card_data.explicit);
+    assert_eq!(card_data.popularity, Some(71));
+    assert_eq!((card_data.disc_number, card_data.track_number), (Some(1), Some(4)));
+    assert_eq!(card_data.isrc.as_deref(), Some("USJT11500162"));
+    assert_eq!(
+        card_data.external_url.as_deref(),
+        Some("https://open.spotify.com/track/7GbcnXvXZTj0bBQkfzpvGK")
+    );
+}
+
+#[tokio::test]
+async fn albums_are_mapped_into_card_data() {
+    let base_url = start("token-1").await;
+    let provider =
+        SpotifyProvider::new(CountingTokens::default()).with_api_url(&format!("{base_url}/v1"));
+
+    let card_data = provider.card(&format!("album:{ALBUM_ID}")).await.unwrap();
+    assert_eq!(card_data.track_id, format!("album:{ALBUM_ID}"));
+    assert_eq!(card_data.kind, CardKind::Album);
+    assert_eq!(card_data.name, "Hunky Dory (2015 Remaster)");
+    assert_eq!(card_data.artists, "David Bowie");
+    assert_eq!(card_data.genres, "#art rock #classic rock #glam rock #permanent wave #rock");
+    assert_eq!(card_data.release_date.as_deref(), Some("1971-12-17"));
+    assert_eq!(card_data.total_tracks, Some(11));
+    // Every page of tracks counts towards the duration.
+    assert_eq!(card_data.duration_ms, Some(2_495_000));
+    assert_eq!(card_data.label.as_deref(), Some("Parlophone UK"));
+    assert_eq!(card_data.popularity, Some(72));
+    assert_eq!(
+        card_data.external_url.as_deref(),
+        Some("https://open.spotify.com/album/6fQElzBNTiEMGdIeY0hy5l")
+    );
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+}
+
+#[tokio::test]
+async fn playlists_are_mapped_into_card_data() {
+    let base_url = start("token-1").await;
+    let provider =
+        SpotifyProvider::new(CountingTokens::default()).with_api_url(&format!("{base_url}/v1"));
+
+    // Repeated albums and local files are left out of the mosaic, the stub has no covers for them.
+    let card_data = provider.card(&format!("playlist:{PLAYLIST_ID}")).await.unwrap();
+    assert_eq!(card_data.track_id, format!("playlist:{PLAYLIST_ID}"));
+    assert_eq!(card_data.kind, CardKind::Playlist);
+    assert_eq!(card_data.name, "Weekly Picks");
+    assert_eq!(card_data.owner.as_deref(), Some("Custom Cards"));
+    assert_eq!(card_data.followers, Some(12345));
+    assert_eq!(card_data.total_tracks, Some(14));
+    assert_eq!(card_data.jacket_size, JACKET_SIZE);
+    let mosaic = image::load_from_memory(&card_data.jacket_bytes).unwrap();
+    assert_eq!((mosaic.width(), mosaic.height()), (640, 640));
+
+    // Unavailable tracks are skipped, local files are listed.
+    assert_eq!(card_data.tracklist.len(), 10);
+    assert_eq!(
+        card_data.tracklist[7],
+        ListedTrack {
+            name: String::from("Get Lucky (feat. Pharrell Williams and Nile Rodgers)"),
+            artists: String::from("Daft Punk, Pharrell Williams, Nile Rodgers"),
+        }
+    );
+    assert_eq!(card_data.tracklist[8].name, "demo take 3");
+}
+
+#[tokio::test]
+async fn artists_are_mapped_into_card_data() {
+    let base_url = start("token-1").await;
+    let provider =
+        SpotifyProvider::new(CountingTokens::default()).with_api_url(&format!("{base_url}/v1"));
+
+    let card_data = provider.card(&format!("artist:{ARTIST_ID}")).await.unwrap();
+    assert_eq!(card_data.track_id, format!("artist:{ARTIST_ID}"));
+    assert_eq!(card_data.kind, CardKind::Artist);
+    assert_eq!(card_data.name, "David Bowie");
+    assert_eq!(card_data.followers, Some(11260412));
+    assert_eq!(card_data.genres, "#art rock #classic rock #glam rock #permanent wave #rock");
+    assert_eq!(card_data.jacket_size, 64);
+    assert_eq!(card_data.jacket_bytes, jacket_png());
+
+    // Only the featured artists are listed next to the top tracks.
+    let tracklist: Vec<_> = card_data
+        .tracklist
+        .iter()
+        .map(|track| (track.name.as_str(), track.artists.as_str()))
+        .collect();
+    assert_eq!(
+        tracklist,
+        [
+            ("Starman - 2012 Remaster", ""),
+            ("Under Pressure - Remastered 2011", "Queen"),
+            ("Life on Mars? - 2015 Remaster", ""),
+            ("Heroes - 2017 Remaster", ""),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn podcasts_are_mapped_into_card_data() {
+    let base_url = start("token-1").await;
+    let provider =
+        SpotifyProvider::new(CountingTokens::default()).with_api_url(&format!("{base_url}/v1"));
+
+    // The episode has no artwork of its own and gets the one of its show.
+    let episode = provider.card(&format!("episode:{EPISODE_ID}")).await.unwrap();
+    assert_eq!(episode.track_id, format!("episode:{EPISODE_ID}"));
+    assert_eq!(episode.kind, CardKind::Episode);
+    assert_eq!(episode.name, "Daft Punk - Harder, Better, Faster, Stronger");
+    assert_eq!(episode.album, "Song Exploder");
+    assert_eq!(episode.artists, "Hrishikesh Hirway");
+    assert_eq!(episode.release_date.as_deref(), Some("2023-10-17"));
+    assert_eq!(episode.duration_ms, Some(1_843_000));
+    assert_eq!(episode.jacket_size, 64);
+    assert_eq!(episode.jacket_bytes, jacket_png());
+
+    let show = provider.card(&format!("show:{SHOW_ID}")).await.unwrap();
+    assert_eq!(show.track_id, format!("show:{SHOW_ID}"));
+    assert_eq!(show.kind, CardKind::Show);
+    assert_eq!(show.name, "Song Exploder");
+    assert_eq!(show.artists, "Hrishikesh Hirway");
+    assert_eq!(show.total_tracks, Some(262));
+    assert_eq!(show.jacket_bytes, jacket_png());
+}
+
+#[tokio::test]
+async fn rejected_tokens_are_renewed_once() {
+    let base_url = start("token-2").await;
+    let tokens = CountingTokens::default();
+    let provider = SpotifyProvider::new(tokens.clone()).with_api_url(&format!("{base_url}/v1"));
+
+    provider.track(TRACK_ID).await.unwrap();
+    provider.track(TRACK_ID).await.unwrap();
+    assert_eq!(tokens.0.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn unknown_tracks_are_not_found() {
+    let base_url = start("token-1").await;
+    let provider =
+        SpotifyProvider::new(CountingTokens::default()).with_api_url(&format!("{base_url}/v1"));
+
+    let result = provider.track("0000000000000000000000").await;
+    assert!(matches!(result, Err(ProviderError::NotFound(_))), "{result:?}");
+}
+
+#[test]
+fn card_ids_are_parsed_from_links() {
+    let provider = SpotifyProvider::new(CountingTokens::default());
+
+    for input in [
+        TRACK_ID,
+        "spotify:track:7GbcnXvXZTj0bBQkfzpvGK",
+        "https://open.spotify.com/track/7GbcnXvXZTj0bBQkfzpvGK",
+        "https://open.spotify.com/track/7GbcnXvXZTj0bBQkfzpvGK?si=0123456789abcdef",
+        "https://open.spotify.com/intl-es/track/7GbcnXvXZTj0bBQkfzpvGK",
+    ] {
+        assert_eq!(provider.parse_track_id(input).as_deref(), Some(TRACK_ID), "{input}");
+    }
+    for input in [
+        "spotify:album:6fQElzBNTiEMGdIeY0hy5l",
+        "https://open.spotify.com/album/6fQElzBNTiEMGdIeY0hy5l?si=0123456789abcdef",
+        "https://open.spotify.com/intl-es/album/6fQElzBNTiEMGdIeY0hy5l",
+    ] {
+        let id = provider.parse_track_id(input);
+        assert_eq!(id.as_deref(), Some("album:6fQElzBNTiEMGdIeY0hy5l"), "{input}");
+    }
+    for input in [
+        "spotify:playlist:37i9dQZF1DXcBWIGoYBM5M",
+        "https://open.spotify.com/playlist/37i9dQZF1DXcBWIGoYBM5M?si=0123456789abcdef",
+    ] {
+        let id = provider.parse_track_id(input);
+        assert_eq!(id.as_deref(), Some("playlist:37i9dQZF1DXcBWIGoYBM5M"), "{input}");
+    }
+    for input in [
+        "spotify:artist:0oSGxfWSnnOXhD2fKuz2Gy",
+        "https://open.spotify.com/intl-fr/artist/0oSGxfWSnnOXhD2fKuz2Gy?si=0123456789abcdef",
+    ] {
+        let id = provider.parse_track_id(input);
+        assert_eq!(id.as_deref(), Some("artist:0oSGxfWSnnOXhD2fKuz2Gy"), "{input}");
+    }
+    for (input, id) in [
+        ("spotify:episode:512ojhOuo1ktJprKbVcKyQ", "episode:512ojhOuo1ktJprKbVcKyQ"),
+        (
+            "https://open.spotify.com/episode/512ojhOuo1ktJprKbVcKyQ",
+            "episode:512ojhOuo1ktJprKbVcKyQ",
+        ),
+        ("spotify:show:5CfCWKI5pZ28U0uOzXkDHe", "show:5CfCWKI5pZ28U0uOzXkDHe"),
+        (
+            "https://open.spotify.com/show/5CfCWKI5pZ28U0uOzXkDHe?si=01234",
+            "show:5CfCWKI5pZ28U0uOzXkDHe",
+        ),
+    ] {
+        assert_eq!(provider.parse_track_id(input).as_deref(), Some(id), "{input}");
+    }
+    for input in [
+        "https://open.spotify.com/audiobook/7iHfbu1YPACw6oZPAFJtqe",
+        "https://www.deezer.com/track/3135556",
+        "7GbcnXvXZTj0bBQkfzpvG",
+    ] {
+        assert_eq!(provider.parse_track_id(input), None, "{input}");
+    }
+}
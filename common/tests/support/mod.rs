@@ -0,0 +1,48 @@
+//! Helpers shared by the card and provider tests.
+#![allow(dead_code)]
+
+use std::{
+    io::Cursor,
+    net::TcpListener,
+    sync::{Arc, OnceLock},
+};
+
+use axum::Router;
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+
+pub fn png(image: RgbaImage) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png).unwrap();
+    bytes
+}
+
+/// 64px jacket with a colorful gradient, encoded as PNG.
+pub fn jacket_png() -> Vec<u8> {
+    png(RgbaImage::from_fn(64, 64, |x, y| Rgba([x as u8 * 4, y as u8 * 4, 128, 255])))
+}
+
+/// Serves `app` on a free local port, standing in for a provider API, and returns its base URL.
+pub async fn serve(app: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+    format!("http://{address}")
+}
+
+/// Base URL of a stub server, known once it listens.
+pub type BaseUrl = Arc<OnceLock<String>>;
+
+/// Serves the router built by `app`, whose handlers can read the base URL from the given state.
+pub async fn serve_with_url(app: impl FnOnce(BaseUrl) -> Router) -> String {
+    let base_url = BaseUrl::default();
+    let url = serve(app(base_url.clone())).await;
+    base_url.set(url.clone()).unwrap();
+    url
+}
+
+/// Recorded provider response from `tests/fixtures`, with `{base_url}` replaced so that the links
+/// it contains point to the stub server.
+pub fn fixture(path: &str, base_url: &str) -> String {
+    let path = format!("{}/tests/fixtures/{path}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(path).unwrap().replace("{base_url}", base_url)
+}
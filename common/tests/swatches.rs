@@ -0,0 +1,90 @@
+use common::colors::Swatches;
+use image::Rgba;
+use kmeans_colors::CentroidData;
+use palette::{Hsl, IntoColor, Lab, Srgb};
+
+/// Centroid of the color with the given hue in degrees, saturation and lightness.
+fn centroid(hue: f32, saturation: f32, lightness: f32, percentage: f32) -> CentroidData<Lab> {
+    let srgb: Srgb = Hsl::new(hue, saturation, lightness).into_color();
+    CentroidData { centroid: srgb.into_color(), percentage, index: 0 }
+}
+
+fn rgba(hue: f32, saturation: f32, lightness: f32) -> Rgba<u8> {
+    let srgb: Srgb = Hsl::new(hue, saturation, lightness).into_color();
+    let srgb = srgb.into_format::<u8>();
+    Rgba([srgb.red, srgb.green, srgb.blue, 255])
+}
+
+#[track_caller]
+fn assert_swatch(swatch: Option<Rgba<u8>>, expected: Rgba<u8>) {
+    let swatch = swatch.expect("swatch is missing");
+    let close = swatch.0.iter().zip(expected.0).all(|(a, b)| a.abs_diff(b) <= 1);
+    assert!(close, "swatch is {swatch:?}, expected {expected:?}");
+}
+
+#[test]
+fn every_swatch_gets_the_closest_centroid() {
+    // Each centroid sits on the target saturation and lightness of one swatch.
+    let centroids = [
+        centroid(0.0, 1.0, 0.5, 0.2),
+        centroid(120.0, 1.0, 0.74, 0.1),
+        centroid(240.0, 1.0, 0.26, 0.1),
+        centroid(30.0, 0.3, 0.5, 0.2),
+        centroid(200.0, 0.3, 0.74, 0.2),
+        centroid(300.0, 0.3, 0.26, 0.2),
+    ];
+
+    let swatches = Swatches::from_centroids(&centroids);
+    assert_swatch(swatches.vibrant, rgba(0.0, 1.0, 0.5));
+    assert_swatch(swatches.light_vibrant, rgba(120.0, 1.0, 0.74));
+    assert_swatch(swatches.dark_vibrant, rgba(240.0, 1.0, 0.26));
+    assert_swatch(swatches.muted, rgba(30.0, 0.3, 0.5));
+    assert_swatch(swatches.light_muted, rgba(200.0, 0.3, 0.74));
+    assert_swatch(swatches.dark_muted, rgba(300.0, 0.3, 0.26));
+
+    let presets = swatches.gradient_presets();
+    let names = presets.iter().map(|(name, _, _)| *name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["Vibrant", "Muted", "Vibrant to Muted", "Muted to Vibrant", "Light", "Dark"]
+    );
+}
+
+#[test]
+fn centroids_are_used_for_a_single_swatch() {
+    // Fits both vibrant and light vibrant, but vibrant is picked first.
+    let swatches = Swatches::from_centroids(&[centroid(0.0, 1.0, 0.6, 1.0)]);
+    assert_swatch(swatches.vibrant, rgba(0.0, 1.0, 0.6));
+    assert_eq!(swatches.light_vibrant, None);
+    assert_eq!(swatches.dark_vibrant, None);
+    assert_eq!(swatches.muted, None);
+    assert_eq!(swatches.light_muted, None);
+    assert_eq!(swatches.dark_muted, None);
+    assert!(swatches.gradient_presets().is_empty());
+}
+
+#[test]
+fn populous_centroids_win_ties() {
+    let centroids = [centroid(0.0, 0.2, 0.26, 0.1), centroid(180.0, 0.2, 0.26, 0.9)];
+
+    let swatches = Swatches::from_centroids(&centroids);
+    assert_swatch(swatches.dark_muted, rgba(180.0, 0.2, 0.26));
+    assert_eq!(swatches.muted, None);
+}
+
+#[test]
+fn presets_skip_missing_swatches() {
+    // Only dark swatches, which still make the "Dark" preset.
+    let centroids = [centroid(240.0, 1.0, 0.26, 0.5), centroid(300.0, 0.3, 0.26, 0.5)];
+
+    let swatches = Swatches::from_centroids(&centroids);
+    assert_swatch(swatches.dark_vibrant, rgba(240.0, 1.0, 0.26));
+    assert_swatch(swatches.dark_muted, rgba(300.0, 0.3, 0.26));
+    let presets = swatches.gradient_presets();
+    assert_eq!(presets.len(), 1);
+    assert_eq!(presets[0].0, "Dark");
+    assert_eq!(
+        (Some(presets[0].1), Some(presets[0].2)),
+        (swatches.dark_vibrant, swatches.dark_muted)
+    );
+}
@@ -0,0 +1,33 @@
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct AngleSliderProps {
+    /// Degrees, following CSS: 0 points up and 90 to the right.
+    pub(crate) angle: f32,
+    pub(crate) onchange: Callback<f32>,
+}
+
+#[function_component]
+pub(crate) fn AngleSlider(AngleSliderProps { angle, onchange }: &AngleSliderProps) -> Html {
+    let on_angle_input = {
+        let onchange = onchange.clone();
+        Callback::from(move |e: InputEvent| {
+            let value = e.target().unwrap().unchecked_into::<HtmlInputElement>().value();
+            if let Ok(angle) = value.parse::<f32>() {
+                onchange.emit(angle);
+            }
+        })
+    };
+
+    html! {
+        <>
+          <p style="text-align:center; width: 12vw; font-size: 1.8vw; margin-top: auto; margin-bottom: auto;">
+            {format!("Angle: {}°", angle.round())}
+          </p>
+          <input type="range" class="form-range mx-2" style="width: 30vw; margin-top: auto; margin-bottom: auto;"
+                 min="0" max="359" value={angle.round().to_string()} oninput={on_angle_input} />
+        </>
+    }
+}
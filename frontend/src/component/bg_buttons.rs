@@ -1,14 +1,16 @@
+use common::model::Background;
 use yew::{function_component, html, Callback, Html, Properties};
 
 #[derive(Properties, PartialEq)]
 pub(crate) struct BackgroundButtonsProps {
-    pub(crate) types: Vec<String>,
-    pub(crate) onclick: Callback<String>,
+    pub(crate) types: Vec<Background>,
+    pub(crate) selected: Background,
+    pub(crate) onclick: Callback<Background>,
 }
 
 #[function_component]
 pub(crate) fn BackgroundButtons(
-    BackgroundButtonsProps { types, onclick }: &BackgroundButtonsProps,
+    BackgroundButtonsProps { types, selected, onclick }: &BackgroundButtonsProps,
 ) -> Html {
     let capitalize = |s: &str| -> String {
         let mut c = s.chars();
@@ -28,11 +30,11 @@ pub(crate) fn BackgroundButtons(
 		};
 		html! {
 			<div>
-				<input type="radio" class="btn-check" name="btnradio" id={format!("btncheck{}",i)} autocomplete="off" />
+				<input type="radio" class="btn-check" name="btnradio" id={format!("btncheck{}",i)} autocomplete="off" checked={bg_type == selected} />
 				<label onclick={on_bg_select}
 			           style="min-width:13vw; font-size:1.8vw"
 				       type="button" class="btn btn-secondary mx-2"
-				       for={format!("btncheck{}",i)}>{capitalize(bg_type)}</label>
+				       for={format!("btncheck{}",i)}>{capitalize(&bg_type.to_string())}</label>
 			</div>
 		}
 	}).collect()
@@ -1,16 +1,31 @@
+use std::rc::Rc;
+
 use base64::{engine::general_purpose, Engine};
 use common::{
-    cards::{CanvasAssets, ColorSelectorEmit, TextAssets},
-    model::{AccessToken, CardData},
+    cards::{to_hex_color, CanvasAssets, ColorSelectorEmit, TextAssets},
+    model::{
+        Background, CardColors, CardData, CardFields, CardKind, CardSpec, ColorStop, GradientShape,
+    },
+    providers::MusicProvider,
 };
+use image::Rgba;
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
+use yew_router::prelude::use_navigator;
 
-use crate::component::{bg_buttons::BackgroundButtons, color_buttons::ColorButtons, dl_button::DownloadButton};
+use crate::{
+    component::{
+        angle_slider::AngleSlider, bg_buttons::BackgroundButtons, dl_button::DownloadButton,
+        field_buttons::FieldButtons, preset_buttons::PresetButtons, shape_buttons::ShapeButtons,
+        stop_editor::StopEditor,
+    },
+    Route,
+};
 
 #[derive(Properties, PartialEq, Debug, Clone)]
 pub struct CardViewProps {
     pub id: String,
+    pub bg: Background,
 }
 
 #[function_component]
@@ -21,48 +36,144 @@ pub(crate) fn CardView(props: &CardViewProps) -> Html {
     let color_btn_style = "justify-content: center; align-items: center; margin-bottom: 1vw; margin-left: auto; margin-right: auto";
     let btn_class = "btn-toolbar mr-1";
     let image = use_state_eq(|| "".to_owned());
-    let prev_bg_type = use_state_eq(|| "".to_owned());
-    let bg_type = use_state_eq(|| "gradient".to_owned());
+    let navigator = use_navigator().unwrap();
+    let prev_bg_type: UseStateHandle<Option<Background>> = use_state_eq(|| None);
+    let bg_type = props.bg.clone();
+    let gradient_shape = use_state_eq(GradientShape::default);
+    // Stops of the custom gradient, empty until the user edits the ones of the palette.
+    let custom_stops: UseStateHandle<Vec<ColorStop>> = use_state_eq(Vec::new);
+    let fields = use_state_eq(CardFields::default);
+    let prev_fields: UseStateHandle<Option<CardFields>> = use_state_eq(|| None);
     let track_id = props.id.to_owned();
     let card_data: UseStateHandle<Option<CardData>> = use_state(|| None);
     let canvas_assets: UseStateHandle<Option<CanvasAssets>> = use_state_eq(|| None);
     let text_assets: UseStateHandle<Option<TextAssets>> = use_state(|| None);
-    let token: UseStateHandle<Option<AccessToken>> = use_state(|| None);
+    let provider =
+        use_state(|| Rc::new(crate::utils::spotify_provider()) as Rc<dyn MusicProvider>);
 
     let bg_types = vec![
-        String::from("plain"),
-        String::from("gradient"),
-        String::from("inverted"),
-        String::from("custom"),
+        Background::Plain,
+        Background::Gradient,
+        Background::Inverted,
+        Background::Custom,
+        Background::BlurredJacket,
     ];
 
     let bg_type_onclick = {
-        let bg_type = bg_type.clone();
-        Callback::from(move |btn_type: String| {
-            bg_type.set(btn_type);
+        let track_id = track_id.clone();
+        Callback::from(move |btn_type: Background| {
+            navigator.push(&Route::CardBackground { id: track_id.clone(), bg: btn_type });
+        })
+    };
+
+    let angle = match *gradient_shape {
+        GradientShape::Linear { angle } | GradientShape::Conic { angle } => angle,
+        GradientShape::Radial => 180.0,
+    };
+    let shapes = vec![
+        (String::from("Linear"), GradientShape::Linear { angle }),
+        (String::from("Radial"), GradientShape::Radial),
+        (String::from("Conic"), GradientShape::Conic { angle }),
+    ];
+
+    let shape_onclick = {
+        let gradient_shape = gradient_shape.clone();
+        Callback::from(move |shape: GradientShape| {
+            gradient_shape.set(shape);
+        })
+    };
+
+    let angle_onchange = {
+        let gradient_shape = gradient_shape.clone();
+        Callback::from(move |angle: f32| {
+            gradient_shape.set(match *gradient_shape {
+                GradientShape::Linear { .. } => GradientShape::Linear { angle },
+                GradientShape::Radial => GradientShape::Radial,
+                GradientShape::Conic { .. } => GradientShape::Conic { angle },
+            });
+        })
+    };
+
+    let fields_onclick = {
+        let fields = fields.clone();
+        Callback::from(move |new_fields: CardFields| {
+            fields.set(new_fields);
+        })
+    };
+
+    // The stops of the palette gradient until the user edits them.
+    let stops: Vec<ColorStop> = if custom_stops.is_empty() {
+        (*canvas_assets)
+            .as_ref()
+            .and_then(|assets| assets.colors.background_stops(&Background::Custom).ok())
+            .unwrap_or_default()
+            .iter()
+            .map(|stop| ColorStop { color: to_hex_color(&stop.color), position: stop.position })
+            .collect()
+    } else {
+        (*custom_stops).clone()
+    };
+
+    let preset_onclick = {
+        let custom_stops = custom_stops.clone();
+        Callback::from(move |(start, end): (Rgba<u8>, Rgba<u8>)| {
+            custom_stops.set(vec![
+                ColorStop { color: to_hex_color(&start), position: 0.0 },
+                ColorStop { color: to_hex_color(&end), position: 1.0 },
+            ]);
         })
     };
 
     let color_onclick = {
-        let canvas_assets = canvas_assets.clone();
+        let custom_stops = custom_stops.clone();
+        let stops = stops.clone();
         Callback::from(move |data: ColorSelectorEmit| {
-            let mut new_canvas_assets = (*canvas_assets).clone();
-            if new_canvas_assets.is_some() {
-                match data.row.as_str() {
-                    "top" => {
-                        let keep_color =
-                            new_canvas_assets.clone().unwrap().colors.gradient_end(true);
-                        new_canvas_assets.as_mut().unwrap().colors.custom_gradient =
-                            Some((data.new_color, keep_color));
-                    }
-                    _ => {
-                        let keep_color =
-                            new_canvas_assets.clone().unwrap().colors.gradient_start(true);
-                        new_canvas_assets.as_mut().unwrap().colors.custom_gradient =
-                            Some((keep_color, data.new_color));
-                    }
-                }
-                canvas_assets.set(new_canvas_assets);
+            let mut new_stops = stops.clone();
+            if let Some(stop) = data.row.parse::<usize>().ok().and_then(|i| new_stops.get_mut(i)) {
+                stop.color = to_hex_color(&data.new_color);
+                custom_stops.set(new_stops);
+            }
+        })
+    };
+
+    let position_onchange = {
+        let custom_stops = custom_stops.clone();
+        let stops = stops.clone();
+        Callback::from(move |(index, position): (usize, f32)| {
+            let mut new_stops = stops.clone();
+            if let Some(stop) = new_stops.get_mut(index) {
+                stop.position = position.clamp(0.0, 1.0);
+                custom_stops.set(new_stops);
+            }
+        })
+    };
+
+    let remove_onclick = {
+        let custom_stops = custom_stops.clone();
+        let stops = stops.clone();
+        Callback::from(move |index: usize| {
+            if stops.len() > 2 && index < stops.len() {
+                let mut new_stops = stops.clone();
+                new_stops.remove(index);
+                custom_stops.set(new_stops);
+            }
+        })
+    };
+
+    // New stops split the widest gap between two stops, starting with the color of the first one.
+    let add_onclick = {
+        let custom_stops = custom_stops.clone();
+        let stops = stops.clone();
+        Callback::from(move |_| {
+            let mut sorted = stops.clone();
+            sorted.sort_by(|a, b| a.position.total_cmp(&b.position));
+            let gap = |pair: &[ColorStop]| pair[1].position - pair[0].position;
+            let widest_gap = sorted.windows(2).max_by(|a, b| gap(a).total_cmp(&gap(b)));
+            if let Some(pair) = widest_gap {
+                let position = (pair[0].position + pair[1].position) / 2.0;
+                let mut new_stops = stops.clone();
+                new_stops.push(ColorStop { color: pair[0].color.clone(), position });
+                custom_stops.set(new_stops);
             }
         })
     };
@@ -73,48 +184,106 @@ pub(crate) fn CardView(props: &CardViewProps) -> Html {
         let text_assets = text_assets.clone();
         let card_data = card_data.clone();
         let bg_type = bg_type.clone();
+        let gradient_shape = *gradient_shape;
+        let fields = (*fields).clone();
+        let custom_stops = (*custom_stops).clone();
+        let provider = (*provider).clone();
 
-        use_effect_with((track_id.clone(), canvas_assets.clone(), bg_type.clone()), move |_| {
+        use_effect_with((track_id.clone(), canvas_assets.clone(), bg_type.clone(), gradient_shape, fields.clone(), custom_stops.clone()), move |_| {
             spawn_local(async move {
+                let spec = CardSpec {
+                    background: bg_type.clone(),
+                    gradient: gradient_shape,
+                    colors: CardColors {
+                        custom_stops: (!custom_stops.is_empty()).then_some(custom_stops),
+                    },
+                    fields: fields.clone(),
+                    ..Default::default()
+                };
                 let mut new_card_data = (*card_data).clone();
                 if new_card_data.clone().is_none()
                     || new_card_data.clone().unwrap().track_id != track_id
                 {
-                    new_card_data = crate::utils::fetch_data(track_id.clone(), token).await.ok();
+                    new_card_data = match provider.card(&track_id).await {
+                        Ok(card_data) => Some(card_data),
+                        Err(err) => {
+                            log::error!("{err}");
+                            return;
+                        }
+                    };
                 }
                 let mut new_canvas_assets = (*canvas_assets).clone();
                 let mut new_text_assets = (*text_assets).clone();
                 if new_card_data.clone() != (*card_data).clone() || (*card_data).clone().is_none() {
                     card_data.set(Some(new_card_data.clone().unwrap()));
                     new_canvas_assets =
-                        Some(common::cards::generate_canvas_assets(new_card_data.clone().unwrap()));
+                        match common::cards::generate_canvas_assets(
+                            new_card_data.clone().unwrap(),
+                            &spec,
+                        ) {
+                            Ok(assets) => Some(assets),
+                            Err(err) => {
+                                log::error!("{err}");
+                                return;
+                            }
+                        };
                     canvas_assets.set(new_canvas_assets.clone());
 
-                    new_text_assets = Some(common::cards::generate_text_assets(
+                    new_text_assets = match common::cards::generate_text_assets(
                         new_card_data.clone().unwrap(),
                         new_canvas_assets.clone().unwrap(),
-                    ));
+                        &spec,
+                    ) {
+                        Ok(assets) => Some(assets),
+                        Err(err) => {
+                            log::error!("{err}");
+                            return;
+                        }
+                    };
                     text_assets.set(new_text_assets.clone());
                 }
-                if (*bg_type).clone() != (*prev_bg_type).clone()
+                // The text scales depend on which lines are drawn.
+                if Some(fields.clone()) != (*prev_fields).clone() {
+                    prev_fields.set(Some(fields.clone()));
+                    if let (Some(card), Some(canvas)) =
+                        (new_card_data.clone(), new_canvas_assets.clone())
+                    {
+                        new_text_assets =
+                            match common::cards::generate_text_assets(card, canvas, &spec) {
+                                Ok(assets) => Some(assets),
+                                Err(err) => {
+                                    log::error!("{err}");
+                                    return;
+                                }
+                            };
+                        text_assets.set(new_text_assets.clone());
+                    }
+                }
+                if Some(bg_type.clone()) != (*prev_bg_type).clone()
                     || new_card_data.clone() != (*card_data).clone()
                     || new_canvas_assets.clone() == (*canvas_assets).clone()
                 {
-                    prev_bg_type.set((*bg_type).clone());
+                    prev_bg_type.set(Some(bg_type.clone()));
+                    // Each of them starts from the current state, so `None` means nothing is
+                    // loaded yet.
+                    let (Some(new_card_data), Some(new_canvas_assets), Some(new_text_assets)) =
+                        (new_card_data, new_canvas_assets, new_text_assets)
+                    else {
+                        return;
+                    };
                     let generated_image = common::cards::generate_card(
-                        (!new_card_data.clone().is_none())
-                            .then_some(new_card_data.clone().unwrap())
-                            .unwrap_or((*card_data).clone().unwrap()),
-                        (!new_canvas_assets.clone().is_none())
-                            .then_some(new_canvas_assets.clone().unwrap())
-                            .unwrap_or((*canvas_assets).clone().unwrap()),
-                        (!new_text_assets.clone().is_none())
-                            .then_some(new_text_assets.clone().unwrap())
-                            .unwrap_or((*text_assets).clone().unwrap()),
-                        (*bg_type).clone(),
+                        new_card_data,
+                        new_canvas_assets,
+                        new_text_assets,
+                        &spec,
                     );
-                    let b64 = general_purpose::STANDARD.encode(&generated_image);
-                    image.set(format!("data:image/png;base64,{}", b64));
+                    match generated_image {
+                        Ok(generated_image) => {
+                            let b64 = general_purpose::STANDARD.encode(&generated_image);
+                            image.set(format!("data:image/png;base64,{}", b64));
+                        }
+                        Err(err) => log::error!("{err}"),
+                    }
                 }
             });
             || ()
@@ -125,20 +294,47 @@ pub(crate) fn CardView(props: &CardViewProps) -> Html {
         <div>
           <div class="row">
              <div class={btn_class} role="toolbar" style={bg_btn_style} aria-label="Background selector">
-               <BackgroundButtons types={bg_types} onclick={bg_type_onclick} />
+               <BackgroundButtons types={bg_types} selected={bg_type.clone()} onclick={bg_type_onclick} />
              </div>
           </div>
-          if (*bg_type).clone() == "custom".to_string() {
+          if (*card_data).as_ref().is_some_and(|card_data| card_data.kind == CardKind::Track) {
            <div class="row">
-             <div class={btn_class} role="toolbar" style={color_btn_style} aria-label="Color selector 1">
-              <p style="text-align:center; width: 12vw; font-size: 1.8vw; margin-top: auto; margin-bottom: auto;">{"Start Color:"}</p>
-              <ColorButtons colors={(*canvas_assets).clone().unwrap().colors.all_colors} onclick={color_onclick.clone()} row={"top"} />
+             <div class={btn_class} role="toolbar" style={color_btn_style} aria-label="Track details">
+              <FieldButtons fields={(*fields).clone()} onclick={fields_onclick} />
              </div>
-             <div class={btn_class} role="toolbar" style={color_btn_style} aria-label="Color selector 2">
-              <p style="text-align:center; width: 12vw; padding-left: 0.8vw; font-size: 1.8vw;margin-top: auto; margin-bottom: auto;">{"End Color:"}</p>
-              <ColorButtons colors={(*canvas_assets).clone().unwrap().colors.all_colors} onclick={color_onclick.clone()} row={"bottom"} />
+           </div>
+          }
+          if bg_type == Background::Custom {
+           <div class="row">
+             <div class={btn_class} role="toolbar" style={color_btn_style} aria-label="Gradient shape selector">
+              <ShapeButtons shapes={shapes} selected={*gradient_shape} onclick={shape_onclick} />
              </div>
+             if *gradient_shape != GradientShape::Radial {
+              <div class={btn_class} role="toolbar" style={color_btn_style} aria-label="Gradient angle">
+               <AngleSlider angle={angle} onchange={angle_onchange} />
+              </div>
+             }
            </div>
+           // Opening a custom card by URL gets here before the jacket colors are known.
+           if let Some(assets) = &*canvas_assets {
+            <div class="row">
+              <div class={btn_class} role="toolbar" style={color_btn_style} aria-label="Color presets">
+               <p style="text-align:center; width: 12vw; font-size: 1.8vw; margin-top: auto; margin-bottom: auto;">{"Presets:"}</p>
+               <PresetButtons presets={assets.colors.swatches.gradient_presets()} onclick={preset_onclick} />
+              </div>
+              { for stops.iter().enumerate().map(|(index, stop)| html! {
+                <div class={btn_class} role="toolbar" style={color_btn_style} aria-label={format!("Gradient stop {}", index + 1)}>
+                 <StopEditor index={index} stop={stop.clone()}
+                             colors={assets.colors.all_colors.clone()}
+                             removable={stops.len() > 2}
+                             oncolor={color_onclick.clone()} onposition={position_onchange.clone()} onremove={remove_onclick.clone()} />
+                </div>
+              }) }
+              <div class={btn_class} role="toolbar" style={color_btn_style} aria-label="Add gradient stop">
+               <button onclick={add_onclick} type="button" class="btn btn-outline-secondary mx-2" style="font-size:1.5vw">{"Add stop"}</button>
+              </div>
+            </div>
+           }
           }
           <div class="row">
             <img src={(*image).clone()} style={style} />
@@ -0,0 +1,43 @@
+use common::model::CardFields;
+use yew::{function_component, html, Callback, Html, Properties};
+
+/// Optional lines of track cards, with the field showing each of them.
+const TOGGLES: [(&str, fn(&mut CardFields) -> &mut bool); 4] = [
+    ("Duration", |fields| &mut fields.duration),
+    ("Year", |fields| &mut fields.release_year),
+    ("Track number", |fields| &mut fields.track_number),
+    ("Explicit", |fields| &mut fields.explicit),
+];
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct FieldButtonsProps {
+    pub(crate) fields: CardFields,
+    pub(crate) onclick: Callback<CardFields>,
+}
+
+#[function_component]
+pub(crate) fn FieldButtons(FieldButtonsProps { fields, onclick }: &FieldButtonsProps) -> Html {
+    TOGGLES.iter().enumerate().map(|(i, (name, field))| {
+		let on_field_toggle = {
+			let onclick = onclick.clone();
+			let fields = fields.clone();
+			let field = *field;
+			Callback::from(move |_| {
+				let mut fields = fields.clone();
+				let shown = field(&mut fields);
+				*shown = !*shown;
+				onclick.emit(fields)
+			})
+		};
+		let checked = *field(&mut fields.clone());
+		html! {
+			<div>
+				<input type="checkbox" class="btn-check" id={format!("fieldcheck{}",i)} autocomplete="off" checked={checked} />
+				<label onclick={on_field_toggle}
+			           style="min-width:10vw; font-size:1.5vw"
+				       type="button" class="btn btn-outline-secondary mx-2"
+				       for={format!("fieldcheck{}",i)}>{*name}</label>
+			</div>
+		}
+	}).collect()
+}
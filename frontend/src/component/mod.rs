@@ -1,8 +1,13 @@
+pub(crate) mod angle_slider;
 pub(crate) mod bg_buttons;
 pub(crate) mod card_view;
 pub(crate) mod color_buttons;
 pub(crate) mod home;
 pub(crate) mod text_input;
 pub(crate) mod dl_button;
+pub(crate) mod field_buttons;
 pub(crate) mod home_bar;
+pub(crate) mod preset_buttons;
 pub(crate) mod github;
+pub(crate) mod shape_buttons;
+pub(crate) mod stop_editor;
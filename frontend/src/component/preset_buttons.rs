@@ -0,0 +1,31 @@
+use common::cards::to_hex_color;
+use image::Rgba;
+use yew::{function_component, html, Callback, Html, Properties};
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct PresetButtonsProps {
+    pub(crate) presets: Vec<(&'static str, Rgba<u8>, Rgba<u8>)>,
+    pub(crate) onclick: Callback<(Rgba<u8>, Rgba<u8>)>,
+}
+
+#[function_component]
+pub(crate) fn PresetButtons(PresetButtonsProps { presets, onclick }: &PresetButtonsProps) -> Html {
+    let onclick = onclick.clone();
+    presets.iter().map(|(name, start, end)| {
+		let button_style = format!(
+			"background-image: linear-gradient({}, {}); min-width:12vw; font-size:1.5vw; color: white; text-shadow: 0 0 0.3vw black;",
+			to_hex_color(start),
+			to_hex_color(end)
+		);
+		let on_preset_select = {
+			let onclick = onclick.clone();
+			let colors = (*start, *end);
+			Callback::from(move |_| {
+				onclick.emit(colors)
+			})
+		};
+		html! {
+			<button style={button_style} onclick={on_preset_select} type="button" class="btn mx-2">{name}</button>
+		}
+	}).collect()
+}
@@ -0,0 +1,32 @@
+use common::model::GradientShape;
+use yew::{function_component, html, Callback, Html, Properties};
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct ShapeButtonsProps {
+    pub(crate) shapes: Vec<(String, GradientShape)>,
+    pub(crate) selected: GradientShape,
+    pub(crate) onclick: Callback<GradientShape>,
+}
+
+#[function_component]
+pub(crate) fn ShapeButtons(ShapeButtonsProps { shapes, selected, onclick }: &ShapeButtonsProps) -> Html {
+    let onclick = onclick.clone();
+    shapes.iter().enumerate().map(|(i, (name, shape))| {
+		let on_shape_select = {
+			let onclick = onclick.clone();
+			let shape = *shape;
+			Callback::from(move |_| {
+				onclick.emit(shape)
+			})
+		};
+		html! {
+			<div>
+				<input type="radio" class="btn-check" name="shaperadio" id={format!("shapecheck{}",i)} autocomplete="off" checked={shape == selected} />
+				<label onclick={on_shape_select}
+			           style="min-width:10vw; font-size:1.5vw"
+				       type="button" class="btn btn-outline-secondary mx-2"
+				       for={format!("shapecheck{}",i)}>{name}</label>
+			</div>
+		}
+	}).collect()
+}
@@ -0,0 +1,57 @@
+use common::{cards::ColorSelectorEmit, model::ColorStop};
+use image::Rgba;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::component::color_buttons::ColorButtons;
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct StopEditorProps {
+    pub(crate) index: usize,
+    pub(crate) stop: ColorStop,
+    pub(crate) colors: Vec<Rgba<u8>>,
+    /// Gradients need at least two stops.
+    pub(crate) removable: bool,
+    /// Emits the color picked for the stop, with its index as `row`.
+    pub(crate) oncolor: Callback<ColorSelectorEmit>,
+    pub(crate) onposition: Callback<(usize, f32)>,
+    pub(crate) onremove: Callback<usize>,
+}
+
+/// Color and position of one stop of the custom gradient.
+#[function_component]
+pub(crate) fn StopEditor(
+    StopEditorProps { index, stop, colors, removable, oncolor, onposition, onremove }: &StopEditorProps,
+) -> Html {
+    let index = *index;
+    let swatch_style = format!(
+        "display: inline-block; width: 1.8vw; height: 1.8vw; border-radius: 50%; vertical-align: middle; background-color: {};",
+        stop.color
+    );
+    let on_position_input = {
+        let onposition = onposition.clone();
+        Callback::from(move |e: InputEvent| {
+            let value = e.target().unwrap().unchecked_into::<HtmlInputElement>().value();
+            if let Ok(percent) = value.parse::<f32>() {
+                onposition.emit((index, percent / 100.0));
+            }
+        })
+    };
+    let on_remove = {
+        let onremove = onremove.clone();
+        Callback::from(move |_| onremove.emit(index))
+    };
+
+    html! {
+        <>
+          <p style="text-align:center; width: 12vw; font-size: 1.8vw; margin-top: auto; margin-bottom: auto;">
+            {format!("Stop {}: ", index + 1)}<span style={swatch_style} />
+          </p>
+          <ColorButtons colors={colors.clone()} onclick={oncolor.clone()} row={index.to_string()} />
+          <input type="range" class="form-range mx-2" style="width: 12vw; margin-top: auto; margin-bottom: auto;"
+                 min="0" max="100" value={(stop.position * 100.0).round().to_string()} oninput={on_position_input} />
+          <button onclick={on_remove} disabled={!removable} type="button" class="btn btn-outline-secondary mx-2" style="font-size:1.5vw">{"Remove"}</button>
+        </>
+    }
+}
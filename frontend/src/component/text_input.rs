@@ -42,7 +42,7 @@ pub(crate) fn TextInput() -> Html {
         <>
            <form id="inputForm" onSubmit="return false;" style={style}>
               <div class="col-8">
-                 <label for="validationInput" class="form-label">{"Put your favorite song!"}</label>
+                 <label for="validationInput" class="form-label">{"Put your favorite song, album, playlist, artist or podcast!"}</label>
                  <div class="input-group">
                     <input type="text" class={&*class} id="inputForm" {onkeypress} placeholder="URI" required=true />
                  </div>
@@ -1,7 +1,7 @@
-mod api;
 mod component;
 mod utils;
 
+use common::model::Background;
 use component::card_view::CardView;
 use component::home::Home;
 use component::text_input::TextInput;
@@ -12,6 +12,8 @@ use crate::component::home_bar::HomeBar;
 
 #[derive(Clone, Routable, PartialEq)]
 enum Route {
+    #[at("/:id/:bg")]
+    CardBackground { id: String, bg: Background },
     #[at("/:id")]
     Card { id: String },
     #[at("/")]
@@ -21,7 +23,8 @@ enum Route {
 fn switch(routes: Route) -> Html {
     match routes {
         Route::Home => html! {<Home />},
-        Route::Card { id } => html! {<CardView id={id} /> },
+        Route::Card { id } => html! {<CardView id={id} bg={Background::default()} /> },
+        Route::CardBackground { id, bg } => html! {<CardView id={id} bg={bg} /> },
     }
 }
 
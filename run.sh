@@ -4,6 +4,8 @@ IFS=$'\n\t'
 
 # export CLIENT_ID=YOUR_CLIENT_ID
 # export CLIENT_SECRET=YOUR_CLIENT_SECRET
+export CARDS_API_URL="${CARDS_API_URL:-http://localhost:8080}"
 
 (trap 'kill 0' SIGINT; \
+ cargo run -p server & \
  bash -c 'cd frontend; trunk serve --public-url "/custom-spotify-cards/"')
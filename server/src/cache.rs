@@ -0,0 +1,122 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use axum::body::Bytes;
+use common::model::CardSpec;
+use sha2::{Digest, Sha256};
+
+/// Cards kept on disk unless [`CardCache::with_dir_capacity`] says otherwise.
+const DEFAULT_DIR_CAPACITY: usize = 4096;
+
+/// Rendered cards kept in memory, and on disk when a directory is given, keyed by a hash of
+/// everything they were rendered from.
+pub struct CardCache {
+    memory: Mutex<MemoryCache>,
+    capacity: usize,
+    dir: Option<PathBuf>,
+    /// Most cards kept in `dir`, the oldest files are deleted first.
+    dir_capacity: usize,
+}
+
+#[derive(Default)]
+struct MemoryCache {
+    cards: HashMap<String, Bytes>,
+    /// Keys from the oldest to the newest insertion, the oldest card is evicted first.
+    order: VecDeque<String>,
+}
+
+impl CardCache {
+    pub fn new(capacity: usize, dir: Option<PathBuf>) -> Self {
+        CardCache {
+            memory: Mutex::new(MemoryCache::default()),
+            capacity,
+            dir,
+            dir_capacity: DEFAULT_DIR_CAPACITY,
+        }
+    }
+
+    /// Limits the number of cards kept on disk, [`DEFAULT_DIR_CAPACITY`] otherwise.
+    pub fn with_dir_capacity(mut self, dir_capacity: usize) -> Self {
+        self.dir_capacity = dir_capacity;
+        self
+    }
+
+    /// Hashes the spec together with what identifies the card content, e.g. a track id or the
+    /// uploaded metadata and jacket. The server version is part of the key so that cards rendered
+    /// by an older renderer are not served from disk after an upgrade.
+    pub(crate) fn key(spec: &CardSpec, content: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(serde_json::to_vec(spec).expect("card specs always serialize"));
+        for part in content {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub(crate) async fn get(&self, key: &str) -> Option<Bytes> {
+        if let Some(card) = self.memory.lock().unwrap().cards.get(key) {
+            return Some(card.clone());
+        }
+
+        let path = self.dir.as_ref()?.join(format!("{key}.png"));
+        let card = Bytes::from(tokio::fs::read(path).await.ok()?);
+        self.remember(key, card.clone());
+        Some(card)
+    }
+
+    pub(crate) async fn insert(&self, key: &str, card: Bytes) -> io::Result<()> {
+        self.remember(key, card.clone());
+        if let Some(dir) = &self.dir {
+            tokio::fs::create_dir_all(dir).await?;
+            tokio::fs::write(dir.join(format!("{key}.png")), card).await?;
+            self.evict_files(dir).await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the oldest cards in `dir` until at most `dir_capacity` are left.
+    async fn evict_files(&self, dir: &Path) -> io::Result<()> {
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.path().extension().is_some_and(|ext| ext == "png") {
+                files.push((entry.metadata().await?.modified()?, entry.path()));
+            }
+        }
+        if files.len() <= self.dir_capacity {
+            return Ok(());
+        }
+
+        files.sort();
+        for (_, path) in &files[..files.len() - self.dir_capacity] {
+            // Another request may have evicted the same file in the meantime.
+            match tokio::fs::remove_file(path).await {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn remember(&self, key: &str, card: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut memory = self.memory.lock().unwrap();
+        if memory.cards.insert(key.to_owned(), card).is_none() {
+            memory.order.push_back(key.to_owned());
+        }
+        while memory.order.len() > self.capacity {
+            if let Some(oldest) = memory.order.pop_front() {
+                memory.cards.remove(&oldest);
+            }
+        }
+    }
+}
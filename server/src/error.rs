@@ -0,0 +1,99 @@
+use std::{error::Error, fmt};
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use common::{cards::CardError, providers::ProviderError, tags::TagError};
+
+#[derive(Debug)]
+pub(crate) enum ServerError {
+    /// The request could not be understood, e.g. an unknown background or a malformed jacket.
+    BadRequest(String),
+    NotFound(String),
+    /// The music provider could not be reached, does not know the track or answered with an
+    /// error.
+    Provider(ProviderError),
+    /// The server was started without Spotify credentials.
+    NoCredentials,
+    Card(CardError),
+    /// An uploaded audio file could not be read, or lacks a title or cover art.
+    Tags(TagError),
+    /// A rendering or tag reading task panicked or was cancelled.
+    Internal(String),
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::BadRequest(reason) => write!(f, "bad request: {reason}"),
+            ServerError::NotFound(what) => write!(f, "{what} not found"),
+            ServerError::Provider(err) => write!(f, "{err}"),
+            ServerError::NoCredentials => write!(f, "no Spotify credentials configured"),
+            ServerError::Card(err) => write!(f, "could not render the card: {err}"),
+            ServerError::Tags(err) => write!(f, "{err}"),
+            ServerError::Internal(reason) => write!(f, "internal error: {reason}"),
+        }
+    }
+}
+
+impl Error for ServerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServerError::Provider(err) => Some(err),
+            ServerError::Card(err) => Some(err),
+            ServerError::Tags(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProviderError> for ServerError {
+    fn from(err: ProviderError) -> Self {
+        ServerError::Provider(err)
+    }
+}
+
+impl From<CardError> for ServerError {
+    fn from(err: CardError) -> Self {
+        ServerError::Card(err)
+    }
+}
+
+impl From<TagError> for ServerError {
+    fn from(err: TagError) -> Self {
+        ServerError::Tags(err)
+    }
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ServerError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ServerError::NotFound(_) => StatusCode::NOT_FOUND,
+            ServerError::Provider(ProviderError::InvalidId(_)) => StatusCode::BAD_REQUEST,
+            ServerError::Provider(ProviderError::NotFound(_)) => StatusCode::NOT_FOUND,
+            ServerError::Provider(ProviderError::Unsupported(_)) => StatusCode::NOT_FOUND,
+            ServerError::Provider(_) => StatusCode::BAD_GATEWAY,
+            ServerError::NoCredentials => StatusCode::SERVICE_UNAVAILABLE,
+            ServerError::Card(err) => match err {
+                CardError::InvalidColor(_)
+                | CardError::InvalidPaletteSize(_)
+                | CardError::InvalidSpec(_) => StatusCode::BAD_REQUEST,
+                // The request is fine, but its jacket or texts cannot be drawn.
+                CardError::JacketDecode(_) | CardError::EmptyPalette | CardError::EmptyText(_) => {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                }
+                CardError::Font | CardError::Encode(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ServerError::Tags(TagError::Read(_)) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ServerError::Tags(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServerError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        if status.is_server_error() {
+            log::error!("{self}");
+        }
+
+        (status, self.to_string()).into_response()
+    }
+}
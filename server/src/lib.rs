@@ -0,0 +1,77 @@
+mod cache;
+mod error;
+mod routes;
+mod token;
+
+use std::sync::Arc;
+
+use axum::{
+    extract::DefaultBodyLimit,
+    routing::{get, post},
+    Router,
+};
+use common::providers::{
+    apple_music::AppleMusicProvider, deezer::DeezerProvider, musicbrainz::MusicBrainzProvider,
+    spotify::SpotifyProvider,
+};
+use tower_http::cors::CorsLayer;
+
+pub use crate::{cache::CardCache, token::TokenBroker};
+
+/// Largest accepted request body, enough for a base64 encoded 2000px jacket.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+/// Largest accepted audio upload, enough for a few minutes of FLAC.
+const MAX_AUDIO_SIZE: usize = 64 * 1024 * 1024;
+
+pub struct AppState {
+    /// Missing when the server runs without Spotify credentials, only `POST /render` works then.
+    spotify: Option<SpotifyProvider<TokenBroker>>,
+    deezer: DeezerProvider,
+    apple_music: AppleMusicProvider,
+    musicbrainz: MusicBrainzProvider,
+    cache: CardCache,
+}
+
+impl AppState {
+    pub fn new(spotify: Option<SpotifyProvider<TokenBroker>>, cache: CardCache) -> Self {
+        AppState {
+            spotify,
+            deezer: DeezerProvider::new(),
+            apple_music: AppleMusicProvider::new(),
+            musicbrainz: MusicBrainzProvider::new(),
+            cache,
+        }
+    }
+
+    pub fn with_deezer(mut self, deezer: DeezerProvider) -> Self {
+        self.deezer = deezer;
+        self
+    }
+
+    pub fn with_apple_music(mut self, apple_music: AppleMusicProvider) -> Self {
+        self.apple_music = apple_music;
+        self
+    }
+
+    pub fn with_musicbrainz(mut self, musicbrainz: MusicBrainzProvider) -> Self {
+        self.musicbrainz = musicbrainz;
+        self
+    }
+}
+
+/// Routes of the card server. `cors` decides which websites may call it from a browser, like the
+/// frontend fetching its tokens.
+pub fn app(state: AppState, cors: CorsLayer) -> Router {
+    Router::new()
+        .route("/card/:file", get(routes::spotify_card))
+        .route("/card/:provider/:file", get(routes::track_card))
+        .route("/render", post(routes::render_card))
+        .route(
+            "/render/audio",
+            post(routes::render_audio).layer(DefaultBodyLimit::max(MAX_AUDIO_SIZE)),
+        )
+        .route("/token", get(routes::token))
+        .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+        .layer(cors)
+        .with_state(Arc::new(state))
+}
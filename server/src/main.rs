@@ -0,0 +1,71 @@
+use std::{error::Error, net::SocketAddr, path::PathBuf};
+
+use axum::http::{HeaderValue, Method};
+use clap::Parser;
+use common::providers::spotify::SpotifyProvider;
+use server::{AppState, CardCache, TokenBroker};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+#[derive(Parser)]
+#[command(about = "Serve Spotify cards over HTTP")]
+struct Args {
+    #[arg(long, env = "BIND_ADDRESS", default_value = "127.0.0.1:8080")]
+    address: SocketAddr,
+    /// Client ID from the Spotify Developer API Portal, needed to render cards by track id and
+    /// to hand out tokens
+    #[arg(long, env = "CLIENT_ID")]
+    client_id: Option<String>,
+    /// Client secret from the Spotify Developer API Portal
+    #[arg(long, env = "CLIENT_SECRET", hide_env_values = true)]
+    client_secret: Option<String>,
+    /// Base URL of the Spotify accounts service
+    #[arg(long, env = "SPOTIFY_ACCOUNTS_URL", default_value = "https://accounts.spotify.com")]
+    accounts_url: String,
+    /// Origins allowed to call the server from a browser, e.g. the frontend deployment. Any
+    /// origin is allowed when none is given
+    #[arg(long, env = "ALLOWED_ORIGINS", value_delimiter = ',')]
+    allowed_origins: Vec<HeaderValue>,
+    /// Directory rendered cards are also written to, so that they survive restarts
+    #[arg(long, env = "CARD_CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+    /// Number of rendered cards kept in memory
+    #[arg(long, default_value_t = 256)]
+    cache_size: usize,
+    /// Number of rendered cards kept in the cache directory, the oldest are deleted first
+    #[arg(long, default_value_t = 4096)]
+    cache_dir_size: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+
+    let spotify = match (args.client_id, args.client_secret) {
+        (Some(id), Some(secret)) => {
+            let tokens = TokenBroker::new(reqwest::Client::new(), &args.accounts_url, id, secret);
+            Some(SpotifyProvider::new(tokens))
+        }
+        _ => {
+            log::warn!("CLIENT_ID or CLIENT_SECRET is missing, only POST /render is available");
+            None
+        }
+    };
+    let cache =
+        CardCache::new(args.cache_size, args.cache_dir).with_dir_capacity(args.cache_dir_size);
+    let state = AppState::new(spotify, cache);
+
+    let allowed_origins = if args.allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(args.allowed_origins)
+    };
+    let cors = CorsLayer::new()
+        .allow_origin(allowed_origins)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([axum::http::header::CONTENT_TYPE]);
+
+    log::info!("listening on {}", args.address);
+    axum::Server::bind(&args.address).serve(server::app(state, cors).into_make_service()).await?;
+    Ok(())
+}
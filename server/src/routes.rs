@@ -0,0 +1,193 @@
+use std::{io::Cursor, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose, Engine};
+use common::{
+    cards::generate_card_from_spec,
+    model::{AccessToken, CardData, CardSpec},
+    providers::{spotify::TokenSource, MusicProvider},
+    tags::AudioTags,
+};
+use serde::Deserialize;
+
+use crate::{cache::CardCache, error::ServerError, AppState};
+
+#[derive(Deserialize)]
+pub(crate) struct CardQuery {
+    /// Background name as accepted by the CLI, e.g. plain, blurred or solid-1db954.
+    bg: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RenderRequest {
+    #[serde(default)]
+    track_id: String,
+    name: String,
+    #[serde(default)]
+    album: String,
+    #[serde(default = "default_album_type")]
+    album_type: String,
+    #[serde(default)]
+    artists: String,
+    #[serde(default)]
+    genres: String,
+    /// Jacket image encoded in base64.
+    jacket: String,
+    #[serde(default)]
+    spec: CardSpec,
+}
+
+fn default_album_type() -> String {
+    String::from("album")
+}
+
+fn png(card: Bytes) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "image/png"), (header::CACHE_CONTROL, "public, max-age=86400")], card)
+}
+
+async fn render(card_data: CardData, spec: CardSpec) -> Result<Bytes, ServerError> {
+    let card = tokio::task::spawn_blocking(move || generate_card_from_spec(card_data, &spec))
+        .await
+        .map_err(|err| ServerError::Internal(format!("card rendering failed: {err}")))??;
+    Ok(Bytes::from(card))
+}
+
+async fn render_cached(
+    state: &AppState, key: String, card_data: CardData, spec: CardSpec,
+) -> Result<Bytes, ServerError> {
+    let card = render(card_data, spec).await?;
+    if let Err(err) = state.cache.insert(&key, card.clone()).await {
+        log::warn!("could not cache card {key}: {err}");
+    }
+    Ok(card)
+}
+
+/// `GET /card/{track_id}.png`, renders the card of a Spotify track, or of an album, playlist,
+/// artist, podcast episode or show for `album:`, `playlist:`, `artist:`, `episode:` and `show:` ids.
+pub(crate) async fn spotify_card(
+    State(state): State<Arc<AppState>>, Path(file): Path<String>, Query(query): Query<CardQuery>,
+) -> Result<Response, ServerError> {
+    provider_card(&state, "spotify", &file, query).await
+}
+
+/// `GET /card/{provider}/{track_id}.png`, renders the card of a `spotify`, `deezer`, `apple` or
+/// `musicbrainz` track.
+pub(crate) async fn track_card(
+    State(state): State<Arc<AppState>>, Path((provider, file)): Path<(String, String)>,
+    Query(query): Query<CardQuery>,
+) -> Result<Response, ServerError> {
+    provider_card(&state, &provider, &file, query).await
+}
+
+async fn provider_card(
+    state: &AppState, provider: &str, file: &str, query: CardQuery,
+) -> Result<Response, ServerError> {
+    let Some(track_id) = file.strip_suffix(".png") else {
+        return Err(ServerError::NotFound(file.to_owned()));
+    };
+    if track_id.is_empty()
+        || track_id.len() > 64
+        || !track_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == ':')
+    {
+        return Err(ServerError::BadRequest(format!("invalid track id {track_id}")));
+    }
+    let provider: &(dyn MusicProvider + Sync) = match provider {
+        "spotify" => state.spotify.as_ref().ok_or(ServerError::NoCredentials)?,
+        "deezer" => &state.deezer,
+        "apple" => &state.apple_music,
+        "musicbrainz" => &state.musicbrainz,
+        _ => return Err(ServerError::NotFound(format!("provider {provider}"))),
+    };
+
+    let mut spec = CardSpec::default();
+    if let Some(bg) = query.bg {
+        spec.background = bg.parse().map_err(ServerError::BadRequest)?;
+    }
+
+    let key = CardCache::key(&spec, &[b"track", provider.name().as_bytes(), track_id.as_bytes()]);
+    if let Some(card) = state.cache.get(&key).await {
+        return Ok(png(card).into_response());
+    }
+    let card_data = provider.card(track_id).await?;
+    Ok(png(render_cached(state, key, card_data, spec).await?).into_response())
+}
+
+/// `POST /render`, renders a card from uploaded metadata, jacket and spec.
+pub(crate) async fn render_card(
+    State(state): State<Arc<AppState>>, Json(request): Json<RenderRequest>,
+) -> Result<impl IntoResponse, ServerError> {
+    request.spec.validate()?;
+    let jacket_bytes = general_purpose::STANDARD
+        .decode(&request.jacket)
+        .map_err(|err| ServerError::BadRequest(format!("jacket is not valid base64: {err}")))?;
+    let (width, _) = image::io::Reader::new(Cursor::new(&jacket_bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .ok_or_else(|| ServerError::BadRequest(String::from("jacket is not a supported image")))?;
+    let jacket_size = u16::try_from(width)
+        .map_err(|_| ServerError::BadRequest(format!("jacket is too large ({width}px wide)")))?;
+
+    let key = CardCache::key(
+        &request.spec,
+        &[
+            b"render",
+            request.name.as_bytes(),
+            request.album.as_bytes(),
+            request.album_type.as_bytes(),
+            request.artists.as_bytes(),
+            request.genres.as_bytes(),
+            &jacket_bytes,
+        ],
+    );
+    if let Some(card) = state.cache.get(&key).await {
+        return Ok(png(card));
+    }
+    let card_data = CardData {
+        track_id: request.track_id,
+        name: request.name,
+        album: request.album,
+        album_type: request.album_type,
+        artists: request.artists,
+        genres: request.genres,
+        jacket_size,
+        jacket_bytes,
+        ..Default::default()
+    };
+    Ok(png(render_cached(&state, key, card_data, request.spec).await?))
+}
+
+/// `POST /render/audio`, renders a card from the tags and cover art of an uploaded MP3, FLAC, M4A
+/// or Ogg file.
+pub(crate) async fn render_audio(
+    State(state): State<Arc<AppState>>, Query(query): Query<CardQuery>, audio: Bytes,
+) -> Result<impl IntoResponse, ServerError> {
+    let mut spec = CardSpec::default();
+    if let Some(bg) = query.bg {
+        spec.background = bg.parse().map_err(ServerError::BadRequest)?;
+    }
+
+    let key = CardCache::key(&spec, &[b"audio", &audio]);
+    if let Some(card) = state.cache.get(&key).await {
+        return Ok(png(card));
+    }
+    let card_data = tokio::task::spawn_blocking(move || AudioTags::from_bytes(audio.into(), None))
+        .await
+        .map_err(|err| ServerError::Internal(format!("tag reading failed: {err}")))??
+        .card_data()?;
+    Ok(png(render_cached(&state, key, card_data, spec).await?))
+}
+
+/// `GET /token`, hands out a Spotify access token for the frontend to query the Web API with.
+pub(crate) async fn token(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AccessToken>, ServerError> {
+    let spotify = state.spotify.as_ref().ok_or(ServerError::NoCredentials)?;
+    Ok(Json(spotify.tokens().token().await?))
+}
@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use common::{
+    model::AccessToken,
+    providers::{spotify::TokenSource, ProviderError},
+};
+use reqwest::Client;
+use tokio::sync::Mutex;
+
+/// Tokens are renewed this long before Spotify expires them.
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+/// Exchanges the client credentials for Spotify access tokens, so that the secret never leaves
+/// the server. A token is shared by every caller until it is about to expire.
+pub struct TokenBroker {
+    http: Client,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    token: Mutex<Option<(AccessToken, Instant)>>,
+}
+
+impl TokenBroker {
+    /// `accounts_url` is the base URL of the Spotify accounts service,
+    /// `https://accounts.spotify.com` outside of tests.
+    pub fn new(http: Client, accounts_url: &str, client_id: String, client_secret: String) -> Self {
+        TokenBroker {
+            http,
+            token_url: format!("{}/api/token", accounts_url.trim_end_matches('/')),
+            client_id,
+            client_secret,
+            token: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl TokenSource for TokenBroker {
+    /// Returns a token valid for at least another minute, with `expires_in` counting the seconds
+    /// left before the broker renews it.
+    async fn token(&self) -> Result<AccessToken, ProviderError> {
+        let mut token = self.token.lock().await;
+        let now = Instant::now();
+        if let Some((token, renew_at)) = token.as_ref().filter(|(_, renew_at)| now < *renew_at) {
+            let expires_in = renew_at.duration_since(now).as_secs() as u32;
+            return Ok(AccessToken { expires_in, ..token.clone() });
+        }
+
+        let new_token = self
+            .http
+            .post(&self.token_url)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| ProviderError::Request(err.to_string()))?
+            .json::<AccessToken>()
+            .await
+            .map_err(|err| ProviderError::Response(err.to_string()))?;
+        let lifetime =
+            Duration::from_secs(new_token.expires_in.into()).saturating_sub(TOKEN_MARGIN);
+        *token = Some((new_token.clone(), now + lifetime));
+        Ok(AccessToken { expires_in: lifetime.as_secs() as u32, ..new_token })
+    }
+}
@@ -0,0 +1,224 @@
+mod support;
+
+use std::{
+    env, fs,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use base64::{engine::general_purpose, Engine};
+use common::{model::AccessToken, providers::spotify::SpotifyProvider};
+use image::{Rgba, RgbaImage};
+use serde_json::json;
+use server::{AppState, CardCache, TokenBroker};
+use support::{encode_png, jacket_png, serve};
+use tower_http::cors::CorsLayer;
+
+const TRACK_ID: &str = "7GbcnXvXZTj0bBQkfzpvGK";
+
+/// Recorded Spotify response shared with the provider tests of `common`, with its links pointing
+/// to `base_url`.
+fn fixture(name: &str, base_url: &str) -> String {
+    let path = format!("{}/../common/tests/fixtures/spotify/{name}", env!("CARGO_MANIFEST_DIR"));
+    fs::read_to_string(path).unwrap().replace("{base_url}", base_url)
+}
+
+/// Stands in for both the Spotify accounts service and the Web API, counting track lookups.
+async fn start_spotify(track_lookups: Arc<AtomicUsize>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let track = fixture("track.json", &base_url);
+    let artist = fixture("artist.json", &base_url);
+    let app = Router::new()
+        .route(
+            "/api/token",
+            post(|| async {
+                Json(AccessToken {
+                    access_token: String::from("token"),
+                    token_type: String::from("Bearer"),
+                    expires_in: 3600,
+                })
+            }),
+        )
+        .route(
+            &format!("/v1/tracks/{TRACK_ID}"),
+            get(|State(lookups): State<Arc<AtomicUsize>>| async move {
+                lookups.fetch_add(1, Ordering::SeqCst);
+                track
+            }),
+        )
+        .route("/v1/artists/:id", get(|| async { artist }))
+        .route("/image/:id", get(|| async { jacket_png() }))
+        .with_state(track_lookups);
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+    base_url
+}
+
+/// Starts a card server with Spotify credentials, returning its URL and the track lookup counter.
+async fn start(cache: CardCache) -> (String, Arc<AtomicUsize>) {
+    let track_lookups = Arc::new(AtomicUsize::new(0));
+    let spotify_url = start_spotify(track_lookups.clone()).await;
+
+    let tokens = TokenBroker::new(
+        reqwest::Client::new(),
+        &spotify_url,
+        String::from("client"),
+        String::from("secret"),
+    );
+    let spotify = SpotifyProvider::new(tokens).with_api_url(&format!("{spotify_url}/v1"));
+    let state = AppState::new(Some(spotify), cache);
+    (serve(server::app(state, CorsLayer::permissive())).await, track_lookups)
+}
+
+/// Empty directory for the disk cache of a test, `name` keeping tests running in parallel apart.
+fn cache_dir(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("card-cache-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+fn cached_files(dir: &Path) -> usize {
+    fs::read_dir(dir).map(|entries| entries.count()).unwrap_or(0)
+}
+
+async fn png(response: reqwest::Response) -> Vec<u8> {
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    let card = response.bytes().await.unwrap().to_vec();
+    assert!(image::load_from_memory(&card).unwrap().width() > 64);
+    card
+}
+
+async fn post_render(server_url: &str, request: &serde_json::Value) -> reqwest::Response {
+    let client = reqwest::Client::new();
+    client.post(format!("{server_url}/render")).json(request).send().await.unwrap()
+}
+
+fn render_request(name: &str, jacket: &[u8]) -> serde_json::Value {
+    json!({
+        "name": name,
+        "album": "Album",
+        "artists": "Artist",
+        "jacket": general_purpose::STANDARD.encode(jacket),
+    })
+}
+
+#[tokio::test]
+async fn track_cards_are_rendered_once() {
+    let (server_url, track_lookups) = start(CardCache::new(16, None)).await;
+
+    let first = png(reqwest::get(format!("{server_url}/card/{TRACK_ID}.png")).await.unwrap()).await;
+    let again = png(reqwest::get(format!("{server_url}/card/{TRACK_ID}.png")).await.unwrap()).await;
+    assert!(first == again);
+    assert_eq!(track_lookups.load(Ordering::SeqCst), 1);
+
+    // Another background is another card.
+    let url = format!("{server_url}/card/{TRACK_ID}.png?bg=plain");
+    let plain = png(reqwest::get(url).await.unwrap()).await;
+    assert!(plain != first);
+    assert_eq!(track_lookups.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn track_cards_need_valid_ids_and_backgrounds() {
+    let (server_url, track_lookups) = start(CardCache::new(16, None)).await;
+
+    let get = |path: String| async move { reqwest::get(path).await.unwrap().status() };
+    assert_eq!(get(format!("{server_url}/card/not%20an%20id.png")).await, StatusCode::BAD_REQUEST);
+    assert_eq!(get(format!("{server_url}/card/{TRACK_ID}")).await, StatusCode::NOT_FOUND);
+    let url = format!("{server_url}/card/{TRACK_ID}.png?bg=sparkles");
+    assert_eq!(get(url).await, StatusCode::BAD_REQUEST);
+    assert_eq!(track_lookups.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn uploaded_jackets_are_rendered_and_cached_on_disk() {
+    let dir = cache_dir("render");
+    let (server_url, _) = start(CardCache::new(0, Some(dir.clone()))).await;
+
+    let request = render_request("Song", &jacket_png());
+    let first = png(post_render(&server_url, &request).await).await;
+    assert_eq!(cached_files(&dir), 1);
+    let again = png(post_render(&server_url, &request).await).await;
+    assert!(again == first);
+    assert_eq!(cached_files(&dir), 1);
+    let cached = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+    assert!(fs::read(cached).unwrap() == first);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn bad_jackets_are_rejected() {
+    let (server_url, _) = start(CardCache::new(16, None)).await;
+    let post = |body: serde_json::Value| {
+        let server_url = server_url.clone();
+        async move { post_render(&server_url, &body).await.status() }
+    };
+
+    let mut not_base64 = render_request("Song", &jacket_png());
+    not_base64["jacket"] = json!("not base64!");
+    assert_eq!(post(not_base64).await, StatusCode::BAD_REQUEST);
+    assert_eq!(post(render_request("Song", b"not an image")).await, StatusCode::BAD_REQUEST);
+
+    // The header is readable but the pixels are cut off.
+    let mut truncated = jacket_png();
+    truncated.truncate(truncated.len() / 2);
+    let status = post(render_request("Song", &truncated)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn unrenderable_requests_are_client_errors() {
+    let (server_url, track_lookups) = start(CardCache::new(16, None)).await;
+
+    let url = format!("{server_url}/card/{TRACK_ID}.png?bg=solid-zz");
+    assert_eq!(reqwest::get(url).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(track_lookups.load(Ordering::SeqCst), 0);
+
+    let blank_name = render_request("   ", &jacket_png());
+    let status = post_render(&server_url, &blank_name).await.status();
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let black = encode_png(RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 255])));
+    let status = post_render(&server_url, &render_request("Song", &black)).await.status();
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn disk_cache_is_bounded() {
+    let dir = cache_dir("bounded");
+    let (server_url, _) = start(CardCache::new(0, Some(dir.clone())).with_dir_capacity(2)).await;
+    for name in ["First", "Second", "Third"] {
+        png(post_render(&server_url, &render_request(name, &jacket_png())).await).await;
+    }
+    assert_eq!(cached_files(&dir), 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn out_of_bounds_specs_are_rejected() {
+    let (server_url, _) = start(CardCache::new(16, None)).await;
+
+    for spec in [
+        json!({ "layout": { "jacket_scale": 0.0 } }),
+        json!({ "palette": { "runs": 1_000_000_000 } }),
+        json!({ "palette": { "colors": 0 } }),
+    ] {
+        let mut request = render_request("Song", &jacket_png());
+        request["spec"] = spec.clone();
+        let response = post_render(&server_url, &request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{spec}");
+    }
+}
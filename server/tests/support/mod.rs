@@ -0,0 +1,26 @@
+//! Helpers shared by the server tests.
+#![allow(dead_code)]
+
+use std::{io::Cursor, net::TcpListener};
+
+use axum::Router;
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+
+pub fn encode_png(image: RgbaImage) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png).unwrap();
+    bytes
+}
+
+/// 64px jacket with a colorful gradient, encoded as PNG.
+pub fn jacket_png() -> Vec<u8> {
+    encode_png(RgbaImage::from_fn(64, 64, |x, y| Rgba([x as u8 * 4, y as u8 * 4, 128, 255])))
+}
+
+/// Serves `app` on a free local port and returns its base URL.
+pub async fn serve(app: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+    format!("http://{address}")
+}
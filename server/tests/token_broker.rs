@@ -0,0 +1,128 @@
+mod support;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    routing::post,
+    Form, Json, Router,
+};
+use common::{model::AccessToken, providers::spotify::SpotifyProvider};
+use serde::Deserialize;
+use server::{AppState, CardCache, TokenBroker};
+use support::serve;
+use tower_http::cors::CorsLayer;
+
+const CLIENT_ID: &str = "client";
+const CLIENT_SECRET: &str = "secret";
+/// `client:secret` encoded in base64.
+const BASIC_AUTH: &str = "Basic Y2xpZW50OnNlY3JldA==";
+
+#[derive(Deserialize)]
+struct TokenForm {
+    grant_type: String,
+}
+
+/// Stand-in for `POST accounts.spotify.com/api/token`, counting the exchanges it performs.
+#[derive(Clone)]
+struct MockAccounts {
+    exchanges: Arc<AtomicUsize>,
+    expires_in: u32,
+}
+
+async fn mock_token(
+    State(accounts): State<MockAccounts>, headers: HeaderMap, Form(form): Form<TokenForm>,
+) -> Result<Json<AccessToken>, StatusCode> {
+    if headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()) != Some(BASIC_AUTH)
+        || form.grant_type != "client_credentials"
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let exchange = accounts.exchanges.fetch_add(1, Ordering::SeqCst) + 1;
+    Ok(Json(AccessToken {
+        access_token: format!("token-{exchange}"),
+        token_type: String::from("Bearer"),
+        expires_in: accounts.expires_in,
+    }))
+}
+
+/// Starts a mock accounts service and a card server exchanging `secret` through it, returning
+/// the card server URL and the exchange counter.
+async fn start(secret: &str, expires_in: u32) -> (String, Arc<AtomicUsize>) {
+    let accounts = MockAccounts { exchanges: Arc::new(AtomicUsize::new(0)), expires_in };
+    let exchanges = accounts.exchanges.clone();
+    let accounts_url =
+        serve(Router::new().route("/api/token", post(mock_token)).with_state(accounts)).await;
+
+    let tokens = TokenBroker::new(
+        reqwest::Client::new(),
+        &accounts_url,
+        CLIENT_ID.to_owned(),
+        secret.to_owned(),
+    );
+    let state = AppState::new(Some(SpotifyProvider::new(tokens)), CardCache::new(0, None));
+    (serve(server::app(state, CorsLayer::permissive())).await, exchanges)
+}
+
+async fn get_token(server_url: &str) -> reqwest::Response {
+    reqwest::get(format!("{server_url}/token")).await.unwrap()
+}
+
+#[tokio::test]
+async fn tokens_are_shared_until_they_expire() {
+    let (server_url, exchanges) = start(CLIENT_SECRET, 3600).await;
+
+    for _ in 0..3 {
+        let token: AccessToken = get_token(&server_url).await.json().await.unwrap();
+        assert_eq!(token.access_token, "token-1");
+        assert_eq!(token.token_type, "Bearer");
+        assert!(token.expires_in > 3000 && token.expires_in < 3600, "{}", token.expires_in);
+    }
+    assert_eq!(exchanges.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn short_lived_tokens_are_renewed() {
+    // Tokens expiring within the renewal margin are never reused.
+    let (server_url, exchanges) = start(CLIENT_SECRET, 30).await;
+
+    let first: AccessToken = get_token(&server_url).await.json().await.unwrap();
+    let second: AccessToken = get_token(&server_url).await.json().await.unwrap();
+    assert_eq!(first.access_token, "token-1");
+    assert_eq!(second.access_token, "token-2");
+    assert_eq!(exchanges.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn rejected_credentials_are_a_gateway_error() {
+    let (server_url, exchanges) = start("wrong", 3600).await;
+
+    assert_eq!(get_token(&server_url).await.status(), StatusCode::BAD_GATEWAY);
+    assert_eq!(exchanges.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn tokens_need_credentials() {
+    let state = AppState::new(None, CardCache::new(0, None));
+    let server_url = serve(server::app(state, CorsLayer::permissive())).await;
+
+    assert_eq!(get_token(&server_url).await.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn browsers_may_fetch_tokens() {
+    let (server_url, _) = start(CLIENT_SECRET, 3600).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{server_url}/token"))
+        .header(header::ORIGIN, "https://example.github.io")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}